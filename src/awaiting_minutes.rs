@@ -0,0 +1,105 @@
+//! The comments that we posted during a channel's meetings that don't link
+//! to the minutes of the meeting yet, which we keep (with the discussion
+//! they were made from) in the state database until RRSAgent (or someone)
+//! tells us where the minutes were published, so that we can add the link
+//! to each of them then, even if we were restarted in between.
+
+use crate::store::Store;
+use crate::TopicData;
+use serde::{Deserialize, Serialize};
+
+/// How long after posting a comment we still expect the minutes of its
+/// meeting to be published.
+const MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// A comment that doesn't link to the minutes of its meeting yet.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct CommentAwaitingMinutes {
+    pub(crate) data: TopicData,
+    // The hidden marker at the end of the comment.
+    pub(crate) marker: String,
+    pub(crate) owner: String,
+    pub(crate) repo: String,
+    pub(crate) issue_url: String,
+    pub(crate) comment_id: i64,
+    // When we posted it, in seconds since the Unix epoch.
+    pub(crate) posted: u64,
+}
+
+/// The comments for |channel| that were posted recently enough (as of
+/// |now|) to still be waiting for the minutes, oldest first.
+fn recent_comments(store: &Store, channel: &str, now: u64) -> Vec<CommentAwaitingMinutes> {
+    let mut comments: Vec<CommentAwaitingMinutes> =
+        store.get("awaiting_minutes", channel).unwrap_or_default();
+    comments.retain(|comment| now.saturating_sub(comment.posted) < MAX_AGE_SECS);
+    comments
+}
+
+/// Keep |comment|, which we just posted for |channel|, until we know where
+/// the minutes are (forgetting the ones posted too long ago to get them).
+pub(crate) fn add(store: &Store, channel: &str, comment: CommentAwaitingMinutes) {
+    let mut comments = recent_comments(store, channel, comment.posted);
+    comments.push(comment);
+    store.put("awaiting_minutes", channel, &comments);
+}
+
+/// Remove the comments for |channel| that are waiting for the minutes (as of
+/// |now|), to add the link to them.
+pub(crate) fn take(store: &Store, channel: &str, now: u64) -> Vec<CommentAwaitingMinutes> {
+    let comments = recent_comments(store, channel, now);
+    store.remove("awaiting_minutes", channel);
+    comments
+}
+
+/// Forget the comment with |comment_id| for |channel|, because it was
+/// deleted.
+pub(crate) fn remove(store: &Store, channel: &str, comment_id: i64) {
+    let mut comments: Vec<CommentAwaitingMinutes> =
+        store.get("awaiting_minutes", channel).unwrap_or_default();
+    let count = comments.len();
+    comments.retain(|comment| comment.comment_id != comment_id);
+    if comments.len() != count {
+        store.put("awaiting_minutes", channel, &comments);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChannelConfig;
+
+    #[test]
+    fn test_comments_awaiting_minutes() {
+        let store = Store::open(None).unwrap();
+        let comment = |number: u32, posted: u64| CommentAwaitingMinutes {
+            data: TopicData::new(
+                "line-height",
+                "CSS Working Group",
+                &ChannelConfig::default(),
+            ),
+            marker: String::new(),
+            owner: String::from("w3c"),
+            repo: String::from("csswg-drafts"),
+            issue_url: format!("https://github.com/w3c/csswg-drafts/issues/{number}"),
+            comment_id: i64::from(number),
+            posted,
+        };
+        let start = 1_700_000_000;
+        add(&store, "#css", comment(1, start));
+        add(&store, "#css", comment(2, start + 60 * 60));
+        add(&store, "#css", comment(3, start));
+        remove(&store, "#css", 3);
+        let comments = take(&store, "#css", start + 2 * 60 * 60);
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].comment_id, 1);
+        assert_eq!(comments[1].comment_id, 2);
+        assert!(take(&store, "#css", start + 2 * 60 * 60).is_empty());
+
+        // The comments from a meeting that never got minutes are forgotten.
+        add(&store, "#fx", comment(5, start));
+        add(&store, "#fx", comment(4, start + 25 * 60 * 60));
+        let comments = take(&store, "#fx", start + 25 * 60 * 60);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].comment_id, 4);
+    }
+}
@@ -0,0 +1,166 @@
+//! Bugs in Bugzilla installations (such as WebKit's, or W3C's older one),
+//! which some groups still track their issues in, so that a "Bug:" (or
+//! "Github:") line can give a bug as the issue that a topic is about, and
+//! the discussion is posted to it as a comment.
+
+use crate::metrics::GithubMetrics;
+use crate::{describe_http_error, BotConfig, GithubType};
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// A bug in a Bugzilla installation.
+pub(crate) struct BugURL {
+    /// The canonical URL of the bug, such as
+    /// "https://bugs.webkit.org/show_bug.cgi?id=12345".
+    pub(crate) url: String,
+    /// The URL of the installation, such as "https://bugs.webkit.org".
+    pub(crate) base: String,
+    pub(crate) id: i64,
+}
+
+impl BugURL {
+    /// Parse the URL of a bug, such as
+    /// "https://www.w3.org/Bugs/Public/show_bug.cgi?id=12345".
+    pub(crate) fn from_string(s: &str) -> Option<BugURL> {
+        static BUG_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^(?P<base>https?://[^/?#]+(/[^?#]*)?)/show_bug\.cgi\?id=(?P<id>[0-9]+)$")
+                .unwrap()
+        });
+        let caps = BUG_URL_RE.captures(s.trim())?;
+        let base = String::from(&caps["base"]);
+        let id = caps["id"].parse::<i64>().ok()?;
+        Some(BugURL {
+            url: format!("{base}/show_bug.cgi?id={id}"),
+            base,
+            id,
+        })
+    }
+
+    /// Whether the bug is in one of |installations|.
+    pub(crate) fn is_in(&self, installations: &[String]) -> bool {
+        installations
+            .iter()
+            .any(|base| base.trim_end_matches('/').eq_ignore_ascii_case(&self.base))
+    }
+}
+
+/// The base URL of the REST API of the installation that |bug| is in, or of
+/// the mock one that the tests use.
+fn rest_url(github_type: GithubType, bug: &BugURL) -> String {
+    match github_type {
+        GithubType::RealGithubConnection => format!("{}/rest", bug.base),
+        GithubType::MockGithubConnection(host) => format!("{host}/bugzilla/rest"),
+    }
+}
+
+/// Our API key for the installation that |bug| is in, if we have one.
+fn api_key<'a>(config: &'a BotConfig, bug: &BugURL) -> Option<&'a str> {
+    config
+        .bugzilla_api_keys
+        .iter()
+        .find(|(base, _)| base.trim_end_matches('/').eq_ignore_ascii_case(&bug.base))
+        .map(|(_, api_key)| api_key.trim())
+}
+
+/// A request to the REST API of the installation that |bug| is in, with
+/// our API key for it, if we have one.
+fn request(
+    config: &BotConfig,
+    bug: &BugURL,
+    request: reqwest::RequestBuilder,
+) -> reqwest::RequestBuilder {
+    let request = request.header(reqwest::header::USER_AGENT, &config.github_uastring);
+    match api_key(config, bug) {
+        Some(api_key) => request.header("X-BUGZILLA-API-KEY", api_key),
+        None => request,
+    }
+}
+
+/// The summary (title) of |bug|, or None if it doesn't exist (or isn't
+/// visible to us).
+pub(crate) async fn fetch_bug_title(
+    config: &BotConfig,
+    github_type: GithubType,
+    metrics: &GithubMetrics,
+    bug: &BugURL,
+) -> Result<Option<String>, String> {
+    #[derive(Deserialize)]
+    struct Bugs {
+        bugs: Vec<Bug>,
+    }
+    #[derive(Deserialize)]
+    struct Bug {
+        summary: String,
+    }
+    let url = format!(
+        "{}/bug/{}?include_fields=summary",
+        rest_url(github_type, bug),
+        bug.id
+    );
+    let response = async {
+        let response = request(config, bug, reqwest::Client::new().get(url))
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        let body = response.text().await.map_err(|err| err.to_string())?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if !status.is_success() {
+            Err(describe_http_error(status, &body))
+        } else {
+            serde_json::from_str::<Bugs>(&body)
+                .map(|bugs| bugs.bugs.into_iter().next().map(|bug| bug.summary))
+                .map_err(|err| err.to_string())
+        }
+    };
+    metrics.time("bugzilla.get_bug", response).await
+}
+
+/// Add a comment with |text| to |bug|.
+pub(crate) async fn post_comment(
+    config: &BotConfig,
+    github_type: GithubType,
+    metrics: &GithubMetrics,
+    bug: &BugURL,
+    text: String,
+) -> Result<(), String> {
+    if api_key(config, bug).is_none() {
+        return Err(format!("I don't have an API key for {}", bug.base));
+    }
+    let url = format!("{}/bug/{}/comment", rest_url(github_type, bug), bug.id);
+    let payload = serde_json::json!({ "comment": text });
+    let response = async {
+        let response = request(config, bug, reqwest::Client::new().post(url))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(describe_http_error(status, &body))
+        }
+    };
+    metrics.time("bugzilla.create_comment", response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bug_url() {
+        let bug =
+            BugURL::from_string("https://www.w3.org/Bugs/Public/show_bug.cgi?id=12345").unwrap();
+        assert_eq!(bug.base, "https://www.w3.org/Bugs/Public");
+        assert_eq!(bug.id, 12345);
+        assert!(bug.is_in(&[String::from("https://www.w3.org/Bugs/Public/")]));
+        assert!(!bug.is_in(&[String::from("https://bugs.webkit.org")]));
+        assert!(BugURL::from_string("https://bugs.webkit.org/show_bug.cgi?id=1#c2").is_none());
+        assert!(BugURL::from_string("https://github.com/w3c/csswg-drafts/issues/1").is_none());
+    }
+}
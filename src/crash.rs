@@ -0,0 +1,90 @@
+//! What happens when the bot panics, either while handling a message or in
+//! one of its tasks: rather than dying silently (which the owners would only
+//! discover when the next meeting starts), it saves what it knows, tells
+//! its owners what went wrong, and quits so that it can be restarted.
+
+use std::any::Any;
+use std::panic::Location;
+use std::sync::{LazyLock, Mutex, PoisonError};
+use tokio::sync::Notify;
+
+/// The summary of the first panic, once there has been one.
+static FIRST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Wakes whatever is waiting in |panicked| when the first panic happens.
+static PANICKED: LazyLock<Notify> = LazyLock::new(Notify::new);
+
+/// How much of a panic message we repeat on IRC.
+const MAX_SUMMARY_CHARS: usize = 200;
+
+/// Record the summary of every panic (after printing it as usual), so that
+/// the bot can report the first one.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let mut first_panic = FIRST_PANIC.lock().unwrap_or_else(PoisonError::into_inner);
+        if first_panic.is_none() {
+            *first_panic = Some(panic_summary(info.payload(), info.location()));
+            PANICKED.notify_one();
+        }
+    }));
+}
+
+/// Wait for the first panic, and return its summary.
+pub(crate) async fn panicked() -> String {
+    loop {
+        if let Some(summary) = FIRST_PANIC
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+        {
+            return summary;
+        }
+        PANICKED.notified().await;
+    }
+}
+
+/// A one-line summary of a panic with |payload| at |location|, short
+/// enough to say on IRC.
+fn panic_summary(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "unknown panic"
+    };
+    let mut summary = message
+        .lines()
+        .next()
+        .unwrap_or("")
+        .chars()
+        .take(MAX_SUMMARY_CHARS)
+        .collect::<String>();
+    if let Some(location) = location {
+        summary.push_str(&format!(" at {}:{}", location.file(), location.line()));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_panic_summary() {
+        let location = Location::caller();
+        assert_eq!(
+            panic_summary(&"index out of bounds", Some(location)),
+            format!("index out of bounds at src/crash.rs:{}", location.line())
+        );
+        let message = String::from("called `Option::unwrap()` on a `None` value\nmore");
+        assert_eq!(
+            panic_summary(&message, None),
+            "called `Option::unwrap()` on a `None` value"
+        );
+        assert_eq!(panic_summary(&42, None), "unknown panic");
+        assert_eq!(panic_summary(&"x".repeat(500), None).len(), 200);
+    }
+}
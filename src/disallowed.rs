@@ -0,0 +1,62 @@
+//! The repositories that people in each channel have recently tried to use
+//! issues in, but that the bot isn't allowed to comment on there.  Someone
+//! trying the same repository again and again is usually a sign that the
+//! channel's allowed repositories are out of date, so we tell the owners.
+
+use crate::store::Store;
+use std::collections::HashMap;
+
+/// How many attempts to use a repository within ATTEMPT_WINDOW_SECS we tell
+/// the owners about.
+pub(crate) const REPORT_ATTEMPTS: usize = 5;
+
+/// How long we count each attempt for, in seconds.
+const ATTEMPT_WINDOW_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Note that someone in |channel| tried to use an issue in |repo|, which
+/// isn't allowed there, at |now| (in seconds since the epoch).  Return
+/// whether that makes REPORT_ATTEMPTS of them in a week, in which case we
+/// start counting again, so that the owners aren't told about every
+/// attempt after that.
+pub(crate) fn note_attempt(store: &Store, channel: &str, repo: &str, now: u64) -> bool {
+    let mut attempts: HashMap<String, Vec<u64>> = store
+        .get("disallowed_repo_attempts", channel)
+        .unwrap_or_default();
+    for times in attempts.values_mut() {
+        times.retain(|time| now.saturating_sub(*time) < ATTEMPT_WINDOW_SECS);
+    }
+    attempts.retain(|_, times| !times.is_empty());
+    let repo = repo.to_ascii_lowercase();
+    let times = attempts.entry(repo.clone()).or_default();
+    times.push(now);
+    let report = times.len() >= REPORT_ATTEMPTS;
+    if report {
+        let _ = attempts.remove(&repo);
+    }
+    store.put("disallowed_repo_attempts", channel, &attempts);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_attempt() {
+        let store = Store::open(None).unwrap();
+        let day = 24 * 60 * 60;
+        for i in 0..4 {
+            assert!(!note_attempt(&store, "#css", "w3c/new-repo", i * day));
+        }
+        assert!(!note_attempt(&store, "#fx", "w3c/new-repo", 4 * day));
+        assert!(note_attempt(&store, "#css", "W3C/new-repo", 4 * day));
+        // We start counting again after reporting.
+        assert!(!note_attempt(&store, "#css", "w3c/new-repo", 5 * day));
+
+        // Attempts more than a week ago don't count.
+        for i in 0..4 {
+            assert!(!note_attempt(&store, "#fx", "w3c/old-repo", i * day));
+        }
+        assert!(!note_attempt(&store, "#fx", "w3c/old-repo", 10 * day));
+    }
+}
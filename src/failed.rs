@@ -0,0 +1,68 @@
+//! The comments that we couldn't post to github, which we keep (with the
+//! text we would have posted) in the state database, so that someone can
+//! try posting them again with "retry failed" instead of reconstructing
+//! them from the IRC log.
+
+use crate::store::Store;
+use serde::{Deserialize, Serialize};
+
+/// A comment that we couldn't post.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct FailedComment {
+    pub(crate) github_url: String,
+    pub(crate) body: String,
+    // The hidden marker in the body, to check whether an attempt to post
+    // it that seemed to fail actually worked.
+    #[serde(default)]
+    pub(crate) marker: String,
+    // The resolutions in the comment, to record once it's posted.
+    pub(crate) resolutions: Vec<String>,
+    // What went wrong the last time we tried to post it.
+    pub(crate) error: String,
+}
+
+/// The comments for |channel| that we couldn't post, oldest first.
+pub(crate) fn failed_comments(store: &Store, channel: &str) -> Vec<FailedComment> {
+    store.get("failed_comments", channel).unwrap_or_default()
+}
+
+/// Keep |comment|, which we couldn't post for |channel|.
+pub(crate) fn add(store: &Store, channel: &str, comment: FailedComment) {
+    let mut comments = failed_comments(store, channel);
+    comments.push(comment);
+    store.put("failed_comments", channel, &comments);
+}
+
+/// Remove the comments for |channel| that we couldn't post, to try posting
+/// them again.
+pub(crate) fn take(store: &Store, channel: &str) -> Vec<FailedComment> {
+    let comments = failed_comments(store, channel);
+    store.remove("failed_comments", channel);
+    comments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_comments() {
+        let store = Store::open(None).unwrap();
+        let comment = |number: u32| FailedComment {
+            github_url: format!("https://github.com/w3c/csswg-drafts/issues/{number}"),
+            body: String::from("The CSS Working Group just discussed `line-height`."),
+            marker: String::new(),
+            resolutions: vec![],
+            error: String::from("HTTP 502 Bad Gateway: Server Error"),
+        };
+        add(&store, "#css", comment(1));
+        add(&store, "#css", comment(2));
+        add(&store, "#fx", comment(3));
+        let comments = take(&store, "#css");
+        assert_eq!(comments.len(), 2);
+        assert!(comments[0].github_url.ends_with("/1"));
+        assert!(comments[1].github_url.ends_with("/2"));
+        assert!(failed_comments(&store, "#css").is_empty());
+        assert_eq!(failed_comments(&store, "#fx").len(), 1);
+    }
+}
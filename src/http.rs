@@ -0,0 +1,447 @@
+//! A small HTTP server that publishes what the bot knows, such as an Atom
+//! feed of the resolutions it has posted for each channel and the state of
+//! the meeting in each channel, and metrics about the bot's requests to
+//! github.
+
+use crate::metrics::GithubMetrics;
+use crate::store::Store;
+use crate::{BotConfig, ChannelData};
+use log::{info, warn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader, Take};
+use tokio::net::{TcpListener, TcpStream};
+
+/// How many resolutions the feed for a channel contains.
+const FEED_LENGTH: u32 = 50;
+
+/// The most bytes of the request line and headers of a request that we
+/// read, so that a client can't make us use memory without limit.
+const MAX_REQUEST_HEAD_BYTES: u64 = 8192;
+
+/// How long a client has to send the request line and headers of its
+/// request, so that it can't keep a connection open forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the server needs to answer requests.
+pub(crate) struct HttpState {
+    pub(crate) config: Arc<BotConfig>,
+    pub(crate) store: Arc<Store>,
+    pub(crate) channel_data: Arc<Mutex<HashMap<String, Weak<RwLock<ChannelData>>>>>,
+    pub(crate) github_metrics: Arc<GithubMetrics>,
+}
+
+/// The response to a request: the status, the content type, and the body.
+type Response = (&'static str, &'static str, String);
+
+/// Listen for HTTP requests at |listen| (such as 127.0.0.1:8080) and answer
+/// them.
+pub(crate) async fn serve(listen: String, state: HttpState) {
+    let listener = match TcpListener::bind(&listen).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            warn!("Unable to listen for HTTP requests on {listen}: {err}");
+            return;
+        }
+    };
+    info!("Listening for HTTP requests on {listen}");
+    let state = Arc::new(state);
+    loop {
+        match listener.accept().await {
+            Ok((tcp_stream, _socket_addr)) => {
+                let _ = tokio::spawn(handle_connection(tcp_stream, state.clone()));
+            }
+            Err(err) => warn!("Unable to accept HTTP connection: {err}"),
+        }
+    }
+}
+
+/// Why we didn't get the request line and headers of a request.
+#[derive(Debug, PartialEq)]
+enum RequestHeadError {
+    /// The connection was closed (or failed) first.
+    Closed,
+    /// They were longer than MAX_REQUEST_HEAD_BYTES.
+    TooLarge,
+}
+
+/// Read a line of the request line and headers of a request from |reader|.
+async fn read_head_line<R: AsyncBufRead + Unpin>(
+    reader: &mut Take<R>,
+) -> Result<String, RequestHeadError> {
+    let mut line = String::new();
+    match reader.read_line(&mut line).await {
+        Err(_) => Err(RequestHeadError::Closed),
+        Ok(_) if line.ends_with('\n') => Ok(line),
+        Ok(_) if reader.limit() == 0 => Err(RequestHeadError::TooLarge),
+        Ok(_) => Err(RequestHeadError::Closed),
+    }
+}
+
+/// Read the request line and headers of a request from |reader| (which
+/// stops reading at the most we accept), and return the request line and
+/// the Host header, if any.
+async fn read_request_head<R: AsyncBufRead + Unpin>(
+    reader: &mut Take<R>,
+) -> Result<(String, Option<String>), RequestHeadError> {
+    let request_line = read_head_line(reader).await?;
+    // We don't accept requests with bodies, so we only need the Host header.
+    let mut host = None;
+    loop {
+        let header = read_head_line(reader).await?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("host") {
+                host = Some(String::from(value.trim()));
+            }
+        }
+    }
+    Ok((request_line, host))
+}
+
+/// Answer the single request on |tcp_stream|.
+async fn handle_connection(mut tcp_stream: TcpStream, state: Arc<HttpState>) {
+    let (reader, mut writer) = tcp_stream.split();
+    let mut reader = BufReader::new(reader).take(MAX_REQUEST_HEAD_BYTES);
+
+    let head = tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut reader)).await;
+    let (status, content_type, body) = match head {
+        Ok(Ok((request_line, host))) => respond_to_request(&state, &request_line, host),
+        Ok(Err(RequestHeadError::Closed)) => return,
+        Ok(Err(RequestHeadError::TooLarge)) => (
+            "400 Bad Request",
+            "text/plain",
+            String::from("Request headers too large\n"),
+        ),
+        Err(_elapsed) => (
+            "408 Request Timeout",
+            "text/plain",
+            String::from("Request timeout\n"),
+        ),
+    };
+    let result = writer
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}; charset=utf-8\r\n\
+                 Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await;
+    if let Err(err) = result {
+        warn!("Unable to respond to HTTP request: {err}");
+    }
+    let _ = writer.shutdown().await;
+}
+
+/// The response to the request with |request_line| and |host| header.
+fn respond_to_request(state: &HttpState, request_line: &str, host: Option<String>) -> Response {
+    let mut request_parts = request_line.split_whitespace();
+    let method = request_parts.next().unwrap_or("");
+    let path = request_parts.next().unwrap_or("");
+    let path = path.split('?').next().unwrap();
+    let host = host.as_deref().unwrap_or("localhost");
+    if method == "GET" {
+        respond(state, host, path)
+    } else {
+        (
+            "405 Method Not Allowed",
+            "text/plain",
+            String::from("Method not allowed\n"),
+        )
+    }
+}
+
+/// The response to a GET request for |path| on |host|.
+fn respond(state: &HttpState, host: &str, path: &str) -> Response {
+    let segments = path.split('/').collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["", "feeds", file] => match file.strip_suffix(".atom") {
+            Some(name) => resolutions_feed(state, &format!("http://{host}{path}"), name),
+            None => not_found(),
+        },
+        ["", "channels", name] => channel_state(state, name),
+        ["", "metrics"] => (
+            "200 OK",
+            "text/plain; version=0.0.4",
+            state.github_metrics.prometheus(),
+        ),
+        _ => not_found(),
+    }
+}
+
+fn not_found() -> Response {
+    ("404 Not Found", "text/plain", String::from("Not found\n"))
+}
+
+/// Escape |s| for use as XML text or in an attribute value.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Format |time| (in seconds since the epoch) as an Atom date.
+fn atom_date(time: i64) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .unwrap_or_default()
+        .format("%Y-%m-%dT%H:%M:%SZ")
+        .to_string()
+}
+
+/// An Atom feed (at |feed_url|) of the recent resolutions that we posted for
+/// the channel #|name|.
+fn resolutions_feed(state: &HttpState, feed_url: &str, name: &str) -> Response {
+    let channel = format!("#{name}");
+    let Some(channel_config) = state.config.channels.get(&channel) else {
+        return not_found();
+    };
+    let resolutions = state.store.recent_resolutions(&channel, FEED_LENGTH);
+    let updated = resolutions.first().map_or(0, |resolution| resolution.time);
+    let feed_url = escape_xml(feed_url);
+    let group = escape_xml(&channel_config.group);
+    let mut feed = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <feed xmlns=\"http://www.w3.org/2005/Atom\">\n\
+         <title>Resolutions of the {group}</title>\n\
+         <id>{feed_url}</id>\n\
+         <link rel=\"self\" href=\"{feed_url}\"/>\n\
+         <author><name>{group}</name></author>\n\
+         <updated>{}</updated>\n",
+        atom_date(updated)
+    );
+    for resolution in resolutions {
+        let link = escape_xml(
+            resolution
+                .comment_url
+                .as_deref()
+                .unwrap_or(&resolution.github_url),
+        );
+        feed.push_str(&format!(
+            "<entry>\n\
+             <title>{}</title>\n\
+             <id>{feed_url}#{}</id>\n\
+             <link href=\"{link}\"/>\n\
+             <updated>{}</updated>\n\
+             <summary>{} (in {})</summary>\n\
+             </entry>\n",
+            escape_xml(&resolution.resolution),
+            resolution.id,
+            atom_date(resolution.time),
+            escape_xml(&resolution.resolution),
+            escape_xml(&resolution.github_url),
+        ));
+    }
+    feed.push_str("</feed>\n");
+    ("200 OK", "application/atom+xml", feed)
+}
+
+/// A JSON description of the state of the channel #|name|: the current
+/// topic and its github URL, how many lines of it we're holding, the agenda,
+/// and when something was last said.
+fn channel_state(state: &HttpState, name: &str) -> Response {
+    let channel = format!("#{name}");
+    if !state.config.channels.contains_key(&channel) {
+        return not_found();
+    }
+    let channel_data = state
+        .channel_data
+        .lock()
+        .unwrap()
+        .get(&channel)
+        .and_then(Weak::upgrade);
+    let json = match channel_data {
+        Some(channel_data) => channel_data.read().unwrap().status_json(),
+        // We haven't seen anything in the channel since we started.
+        None => serde_json::json!({
+            "channel": channel,
+            "topic": null,
+            "github_url": null,
+            "lines": 0,
+            "agenda": state.store.get::<Vec<String>>("agenda", &channel).unwrap_or_default(),
+            "last_activity": null,
+        }),
+    };
+    ("200 OK", "application/json", json.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChannelConfig, GithubType, TopicData};
+
+    fn test_config() -> Arc<BotConfig> {
+        Arc::new(BotConfig {
+            channels: [(
+                String::from("#css"),
+                ChannelConfig {
+                    group: String::from("CSS Working Group"),
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_read_request_head() {
+        let read = |request: &'static [u8]| async move {
+            read_request_head(&mut request.take(MAX_REQUEST_HEAD_BYTES)).await
+        };
+        assert_eq!(
+            read(b"GET /metrics HTTP/1.1\r\nHost: bot.example.org\r\n\r\n").await,
+            Ok((
+                String::from("GET /metrics HTTP/1.1\r\n"),
+                Some(String::from("bot.example.org"))
+            ))
+        );
+        assert_eq!(
+            read(b"GET /metrics HTTP/1.1\r\nHost: bot").await,
+            Err(RequestHeadError::Closed)
+        );
+        let long_header = format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(10000));
+        assert_eq!(
+            read(long_header.leak().as_bytes()).await,
+            Err(RequestHeadError::TooLarge)
+        );
+
+        // A client that never finishes its headers runs out of time.
+        let (_client, server) = tokio::io::duplex(64);
+        let mut reader = BufReader::new(server).take(MAX_REQUEST_HEAD_BYTES);
+        assert!(
+            tokio::time::timeout(REQUEST_TIMEOUT, read_request_head(&mut reader))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_resolutions_feed() {
+        let store = Store::open(None).unwrap();
+        let url = "https://github.com/w3c/csswg-drafts/issues/1";
+        store.add_resolutions(
+            "#css",
+            url,
+            Some(&format!("{url}#issuecomment-5")),
+            &[
+                String::from("RESOLVED: use <length> & <percentage>"),
+                String::from("ACTION: fantasai to edit"),
+            ],
+        );
+        let state = HttpState {
+            config: test_config(),
+            store: Arc::new(store),
+            channel_data: Arc::new(Mutex::new(HashMap::new())),
+            github_metrics: Arc::default(),
+        };
+
+        let (status, content_type, feed) = respond(&state, "bot.example.org", "/feeds/css.atom");
+        assert_eq!(status, "200 OK");
+        assert_eq!(content_type, "application/atom+xml");
+        assert!(feed.contains("<title>Resolutions of the CSS Working Group</title>"));
+        assert!(feed.contains("<id>http://bot.example.org/feeds/css.atom</id>"));
+        assert!(
+            feed.contains("<title>RESOLVED: use &lt;length&gt; &amp; &lt;percentage&gt;</title>")
+        );
+        assert!(feed.contains(&format!("<link href=\"{url}#issuecomment-5\"/>")));
+        assert!(!feed.contains("ACTION"));
+        assert_eq!(feed.matches("<entry>").count(), 1);
+
+        assert_eq!(
+            respond(&state, "bot.example.org", "/feeds/fx.atom").0,
+            "404 Not Found"
+        );
+        assert_eq!(
+            respond(&state, "bot.example.org", "/feeds/css").0,
+            "404 Not Found"
+        );
+    }
+
+    #[test]
+    fn test_channel_state() {
+        let store = Arc::new(Store::open(None).unwrap());
+        let url = "https://github.com/w3c/csswg-drafts/issues/1";
+        store.put("agenda", "#css", &vec![url]);
+        let config = test_config();
+        let state = HttpState {
+            config: config.clone(),
+            store: store.clone(),
+            channel_data: Arc::new(Mutex::new(HashMap::new())),
+            github_metrics: Arc::default(),
+        };
+        let json = |path| {
+            let (status, content_type, body) = respond(&state, "bot.example.org", path);
+            assert_eq!(status, "200 OK");
+            assert_eq!(content_type, "application/json");
+            serde_json::from_str::<serde_json::Value>(&body).unwrap()
+        };
+
+        let before = json("/channels/css");
+        assert_eq!(before["topic"], serde_json::Value::Null);
+        assert_eq!(before["agenda"], serde_json::json!([url]));
+        assert_eq!(before["last_activity"], serde_json::Value::Null);
+
+        let channel_data = Arc::new_cyclic(|self_ref| {
+            RwLock::new(ChannelData::new(
+                "#css",
+                config.clone(),
+                GithubType::RealGithubConnection,
+                self_ref.clone(),
+                store.clone(),
+                state.github_metrics.clone(),
+                Arc::default(),
+                state.channel_data.clone(),
+            ))
+        });
+        let mut topic =
+            TopicData::new("line-height", "CSS Working Group", &config.channels["#css"]);
+        topic.github_url = Some(String::from(url));
+        channel_data.write().unwrap().current_topic = Some(topic);
+        let _ = state
+            .channel_data
+            .lock()
+            .unwrap()
+            .insert(String::from("#css"), Arc::downgrade(&channel_data));
+
+        let during = json("/channels/css");
+        assert_eq!(during["channel"], "#css");
+        assert_eq!(during["topic"], "line-height");
+        assert_eq!(during["github_url"], url);
+        assert_eq!(during["lines"], 0);
+        assert_eq!(during["agenda"], serde_json::json!([url]));
+        assert!(during["last_activity"].as_u64().unwrap() > 0);
+
+        assert_eq!(
+            respond(&state, "bot.example.org", "/channels/fx").0,
+            "404 Not Found"
+        );
+    }
+
+    #[test]
+    fn test_metrics() {
+        let state = HttpState {
+            config: test_config(),
+            store: Arc::new(Store::open(None).unwrap()),
+            channel_data: Arc::new(Mutex::new(HashMap::new())),
+            github_metrics: Arc::default(),
+        };
+        let future = state
+            .github_metrics
+            .time("issues.get", async { Err::<(), ()>(()) });
+        let _ = futures::executor::block_on(future);
+
+        let (status, _, body) = respond(&state, "bot.example.org", "/metrics");
+        assert_eq!(status, "200 OK");
+        assert!(
+            body.contains("github_requests_total{endpoint=\"issues.get\",outcome=\"failure\"} 1\n")
+        );
+    }
+}
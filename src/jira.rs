@@ -0,0 +1,174 @@
+//! Issues in JIRA Cloud sites (such as https://example.atlassian.net), for
+//! channels whose issues live in JIRA, so that "take up" and "Github:" (or
+//! "Bug:") lines can give a JIRA issue as the one that a topic is about,
+//! and the discussion is posted to it as a comment.
+
+use crate::metrics::GithubMetrics;
+use crate::{describe_http_error, BotConfig, GithubType, JiraCredentials};
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::LazyLock;
+
+/// An issue in a JIRA site.
+pub(crate) struct JiraURL {
+    /// The canonical URL of the issue, such as
+    /// "https://example.atlassian.net/browse/ABC-123".
+    pub(crate) url: String,
+    /// The URL of the site, such as "https://example.atlassian.net".
+    pub(crate) base: String,
+    /// The key of the issue, such as "ABC-123".
+    pub(crate) key: String,
+}
+
+impl JiraURL {
+    /// Parse the URL of an issue, such as
+    /// "https://example.atlassian.net/browse/ABC-123".
+    pub(crate) fn from_string(s: &str) -> Option<JiraURL> {
+        static JIRA_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"^(?P<base>https://[^/?#]+)/browse/(?P<key>[A-Z][A-Z0-9_]*-[0-9]+)$")
+                .unwrap()
+        });
+        let caps = JIRA_URL_RE.captures(s.trim())?;
+        let base = String::from(&caps["base"]);
+        let key = String::from(&caps["key"]);
+        Some(JiraURL {
+            url: format!("{base}/browse/{key}"),
+            base,
+            key,
+        })
+    }
+
+    /// Whether the issue is in one of |sites|.
+    pub(crate) fn is_in(&self, sites: &[String]) -> bool {
+        sites
+            .iter()
+            .any(|base| base.trim_end_matches('/').eq_ignore_ascii_case(&self.base))
+    }
+}
+
+/// The base URL of the REST API of the site that |issue| is in, or of the
+/// mock one that the tests use.
+fn rest_url(github_type: GithubType, issue: &JiraURL) -> String {
+    match github_type {
+        GithubType::RealGithubConnection => format!("{}/rest/api/2", issue.base),
+        GithubType::MockGithubConnection(host) => format!("{host}/jira/rest/api/2"),
+    }
+}
+
+/// Our credentials for the site that |issue| is in, if we have them.
+fn credentials<'a>(config: &'a BotConfig, issue: &JiraURL) -> Option<&'a JiraCredentials> {
+    config
+        .jira_credentials
+        .iter()
+        .find(|(base, _)| base.trim_end_matches('/').eq_ignore_ascii_case(&issue.base))
+        .map(|(_, credentials)| credentials)
+}
+
+/// A request to the REST API of the site that |issue| is in, authenticated
+/// with an API token if we have one for it.
+fn request(
+    config: &BotConfig,
+    issue: &JiraURL,
+    request: reqwest::RequestBuilder,
+) -> reqwest::RequestBuilder {
+    let request = request
+        .header(reqwest::header::USER_AGENT, &config.github_uastring)
+        .header(reqwest::header::ACCEPT, "application/json");
+    match credentials(config, issue) {
+        Some(credentials) => {
+            request.basic_auth(&credentials.email, Some(credentials.api_token.trim()))
+        }
+        None => request,
+    }
+}
+
+/// The summary (title) of |issue|, or None if it doesn't exist (or isn't
+/// visible to us).
+pub(crate) async fn fetch_issue_title(
+    config: &BotConfig,
+    github_type: GithubType,
+    metrics: &GithubMetrics,
+    issue: &JiraURL,
+) -> Result<Option<String>, String> {
+    #[derive(Deserialize)]
+    struct Issue {
+        fields: Fields,
+    }
+    #[derive(Deserialize)]
+    struct Fields {
+        summary: String,
+    }
+    let url = format!(
+        "{}/issue/{}?fields=summary",
+        rest_url(github_type, issue),
+        issue.key
+    );
+    let response = async {
+        let response = request(config, issue, reqwest::Client::new().get(url))
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        let body = response.text().await.map_err(|err| err.to_string())?;
+        if status == reqwest::StatusCode::NOT_FOUND {
+            Ok(None)
+        } else if !status.is_success() {
+            Err(describe_http_error(status, &body))
+        } else {
+            serde_json::from_str::<Issue>(&body)
+                .map(|issue| Some(issue.fields.summary))
+                .map_err(|err| err.to_string())
+        }
+    };
+    metrics.time("jira.get_issue", response).await
+}
+
+/// Add a comment with |text| (in JIRA's wiki markup) to |issue|.
+pub(crate) async fn post_comment(
+    config: &BotConfig,
+    github_type: GithubType,
+    metrics: &GithubMetrics,
+    issue: &JiraURL,
+    text: String,
+) -> Result<(), String> {
+    if credentials(config, issue).is_none() {
+        return Err(format!("I don't have an API token for {}", issue.base));
+    }
+    let url = format!(
+        "{}/issue/{}/comment",
+        rest_url(github_type, issue),
+        issue.key
+    );
+    let payload = serde_json::json!({ "body": text });
+    let response = async {
+        let response = request(config, issue, reqwest::Client::new().post(url))
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(describe_http_error(status, &body))
+        }
+    };
+    metrics.time("jira.create_comment", response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jira_url() {
+        let issue = JiraURL::from_string("https://example.atlassian.net/browse/ABC-123").unwrap();
+        assert_eq!(issue.base, "https://example.atlassian.net");
+        assert_eq!(issue.key, "ABC-123");
+        assert!(issue.is_in(&[String::from("https://example.atlassian.net/")]));
+        assert!(!issue.is_in(&[String::from("https://other.atlassian.net")]));
+        assert!(JiraURL::from_string("https://example.atlassian.net/browse/abc-123").is_none());
+        assert!(JiraURL::from_string("https://example.atlassian.net/browse/ABC").is_none());
+    }
+}
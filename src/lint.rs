@@ -0,0 +1,144 @@
+//! Checks for gaps in the minutes of a meeting that mean that what was
+//! decided won't make it to github, so that the chairs can fix them while
+//! people still remember what was said.
+
+use crate::minutes::is_resolution;
+use crate::{
+    check_github_url, count_of, strip_ci_prefix, strip_one_ci_prefix, BotConfig, ChannelLine,
+};
+
+/// The topic that the minutes are in, while we go through them.
+struct LintTopic {
+    topic: String,
+    resolutions: usize,
+    // Whether a "Github:" line gave a URL (or said "none", deliberately
+    // not giving one).
+    has_github_line: bool,
+}
+
+impl LintTopic {
+    /// The problem with the topic, if it had resolutions that won't be
+    /// posted to github because it had no github URL.
+    fn problem(&self) -> Option<String> {
+        (self.resolutions > 0 && !self.has_github_line).then(|| {
+            format!(
+                "The topic \"{}\" has {} but no \"Github:\" line.",
+                self.topic,
+                count_of(self.resolutions, "resolution")
+            )
+        })
+    }
+}
+
+/// Describe the problems in the minutes of a meeting in |channel|, which
+/// are the |lines| said in it: topics with resolutions but no github URL,
+/// github URLs that we can't comment on, and resolutions recorded outside
+/// any topic.
+pub fn lint_meeting(config: &BotConfig, channel: &str, lines: &[ChannelLine]) -> Vec<String> {
+    let mut problems = vec![];
+    let mut current_topic: Option<LintTopic> = None;
+    for line in lines.iter().filter(|line| !line.is_action) {
+        let message = &line.message;
+        let new_topic =
+            strip_ci_prefix(message, "topic:").or_else(|| strip_ci_prefix(message, "subtopic:"));
+        if let Some(topic) = new_topic {
+            problems.extend(current_topic.take().and_then(|topic| topic.problem()));
+            current_topic = Some(LintTopic {
+                topic,
+                resolutions: 0,
+                has_github_line: false,
+            });
+        } else if let Some(url) = strip_one_ci_prefix(
+            message,
+            ["github:", "github topic:", "github issue:"].iter(),
+        ) {
+            let is_usable = url.eq_ignore_ascii_case("none")
+                || match check_github_url(&url, config, channel) {
+                    (Some(Some(_)), None) => true,
+                    (_, error) => {
+                        problems.push(format!(
+                            "{} said \"{message}\", but {}",
+                            line.source,
+                            error.unwrap_or_else(|| String::from("that isn't a github URL."))
+                        ));
+                        false
+                    }
+                };
+            if let (true, Some(ref mut topic)) = (is_usable, &mut current_topic) {
+                topic.has_github_line = true;
+            }
+        } else if is_resolution(message) {
+            match current_topic {
+                Some(ref mut topic) => topic.resolutions += 1,
+                None => problems.push(format!(
+                    "{} recorded \"{message}\" outside of any topic.",
+                    line.source
+                )),
+            }
+        }
+    }
+    problems.extend(current_topic.and_then(|topic| topic.problem()));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChannelConfig;
+
+    fn line(source: &str, message: &str) -> ChannelLine {
+        ChannelLine {
+            source: String::from(source),
+            is_action: false,
+            message: String::from(message),
+        }
+    }
+
+    #[test]
+    fn test_lint_meeting() {
+        let config = BotConfig {
+            channels: [(
+                String::from("#css"),
+                ChannelConfig {
+                    github_repos_allowed: vec![String::from("w3c/csswg-drafts")],
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let lines = [
+            line("chair", "RESOLVED: publish a new WD"),
+            line("chair", "Topic: line-height"),
+            line(
+                "chair",
+                "Github: https://github.com/w3c/csswg-drafts/issues/1",
+            ),
+            line("dbaron", "RESOLVED: line-height is fine"),
+            line("chair", "Topic: grid gaps"),
+            line("dbaron", "RESOLVED: gaps are gutters"),
+            line("chair", "Topic: fonts"),
+            line(
+                "chair",
+                "Github: https://github.com/w3c/fxtf-drafts/issues/2",
+            ),
+            line("chair", "Topic: ruby"),
+            line("chair", "Github: none"),
+            line("dbaron", "RESOLVED: no change"),
+        ];
+        let problems = lint_meeting(&config, "#css", &lines);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(
+            problems[0],
+            "chair recorded \"RESOLVED: publish a new WD\" outside of any topic."
+        );
+        assert_eq!(
+            problems[1],
+            "The topic \"grid gaps\" has 1 resolution but no \"Github:\" line."
+        );
+        assert!(problems[2].starts_with(
+            "chair said \"Github: https://github.com/w3c/fxtf-drafts/issues/2\", but "
+        ));
+    }
+}
@@ -11,31 +11,77 @@
 //! An IRC bot that posts comments to github when W3C-style IRC minuting is
 //! combined with "Github topic:" or "Github issue:" lines that give the
 //! github issue to comment in.
+//!
+//! With "replay <log file> --channel <channel>" after its usual arguments,
+//! it instead reads the IRC log of a meeting (in RRSAgent's text format)
+//! that it missed, and prints the github comments it would have posted, or
+//! posts them if also given "--post".  With "lint <log file> --channel
+//! <channel>", it instead prints the problems in the minutes in such a log
+//! that mean that some of the meeting won't be posted to github.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use futures::prelude::*;
 use irc::client::prelude::{Client as IrcClient, Config as IrcConfig};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
+use std::panic::{self, AssertUnwindSafe};
 use std::str;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use wgmeeting_github_ircbot::*;
 
-fn read_config() -> (IrcConfig, BotConfig) {
-    let mut args = env::args_os();
-    if args.len() != 3 {
+/// The IRC log to replay, for the replay and lint subcommands.
+struct Replay {
+    log_file: String,
+    channel: String,
+    // Whether to post the comments (rather than just printing them).
+    post: bool,
+    // Whether to only check the minutes (rather than replaying them).
+    lint: bool,
+}
+
+/// Parse the arguments after "replay" or (if |lint|) "lint".
+fn parse_replay_args(args: &[String], lint: bool) -> Option<Replay> {
+    let mut log_file = None;
+    let mut channel = None;
+    let mut post = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--channel" => channel = Some(args.next()?.clone()),
+            "--post" if !lint => post = true,
+            _ if log_file.is_none() && !arg.starts_with("--") => log_file = Some(arg.clone()),
+            _ => return None,
+        }
+    }
+    Some(Replay {
+        log_file: log_file?,
+        channel: channel?,
+        post,
+        lint,
+    })
+}
+
+fn read_config() -> (IrcConfig, BotConfig, Option<Replay>) {
+    let args = env::args().collect::<Vec<_>>();
+    let replay = match args.get(3).map(String::as_str) {
+        None if args.len() == 3 => None,
+        Some("replay") => parse_replay_args(&args[4..], false),
+        Some("lint") => parse_replay_args(&args[4..], true),
+        _ => None,
+    };
+    if args.len() < 3 || (args.len() > 3 && replay.is_none()) {
         eprintln!(
-            "syntax: {} <config file> <github access token file>\n",
-            env::args().next().unwrap()
+            "syntax: {} <config file> <github access token file> \
+             [replay <log file> --channel <channel> [--post] | \
+             lint <log file> --channel <channel>]\n",
+            args[0]
         );
         ::std::process::exit(1);
     }
-    let (_, config_file, token_file) = (
-        args.next().unwrap(),
-        args.next().unwrap(),
-        args.next().unwrap(),
-    );
+    let (config_file, token_file) = (&args[1], &args[2]);
 
     #[derive(Deserialize)]
     struct Config {
@@ -51,26 +97,144 @@ fn read_config() -> (IrcConfig, BotConfig) {
         fs::read_to_string(token_file).expect("couldn't read github access token file");
     config.irc.channels = config.channels.keys().cloned().collect();
     config.bot.channels = config.channels;
-    (config.irc, config.bot)
+    config.bot.irc_nicks = config
+        .irc
+        .nickname
+        .iter()
+        .chain(&config.irc.alt_nicks)
+        .cloned()
+        .collect();
+    (config.irc, config.bot, replay)
+}
+
+/// Read the IRC log in |replay|.
+fn read_log(replay: &Replay) -> Result<Vec<ChannelLine>> {
+    let log = fs::read_to_string(&replay.log_file)?;
+    Ok(log.lines().filter_map(parse_log_line).collect())
+}
+
+/// Print the problems in the minutes in the IRC log in |replay|.
+fn lint(bot_config: &BotConfig, replay: &Replay) -> Result<()> {
+    if !bot_config.channels.contains_key(&replay.channel) {
+        bail!("{} isn't in the configuration file", replay.channel);
+    }
+    let problems = lint_meeting(bot_config, &replay.channel, &read_log(replay)?);
+    if problems.is_empty() {
+        println!("No problems found.");
+    }
+    for problem in problems {
+        println!("{problem}");
+    }
+    Ok(())
+}
+
+/// Set up the configuration to replay the log in |replay| instead of
+/// connecting to IRC: the bot reads the lines of the log from a mock IRC
+/// connection, and (unless asked to post them) prints the comments instead
+/// of posting them.
+fn prepare_replay(
+    irc_config: &mut IrcConfig,
+    bot_config: &mut BotConfig,
+    replay: &Replay,
+) -> Result<()> {
+    let channel = &replay.channel;
+    let Some(channel_config) = bot_config.channels.get_mut(channel) else {
+        bail!("{channel} isn't in the configuration file");
+    };
+    // Nobody is around to say to publish, so post each topic when it ends.
+    channel_config.confirm_before_posting = false;
+    channel_config.publish_delay_minutes = 0;
+    bot_config.activity_timeout_minutes = 0;
+    bot_config.dry_run = !replay.post;
+
+    let mut lines = read_log(replay)?;
+    // End the meeting if the log doesn't, so that the last topic is posted.
+    let is_topic = |line: &ChannelLine| {
+        let message = line.message.to_lowercase();
+        !line.is_action && (message.starts_with("topic:") || message.starts_with("subtopic:"))
+    };
+    if lines.iter().rposition(ends_meeting) < lines.iter().rposition(is_topic) {
+        lines.push(ChannelLine {
+            source: String::from("trackbot"),
+            is_action: true,
+            message: String::from("is ending a teleconference."),
+        });
+    }
+
+    irc_config.use_mock_connection = true;
+    irc_config.mock_initial_value = Some(
+        lines
+            .iter()
+            .map(|line| {
+                let message = if line.is_action {
+                    format!("\x01ACTION {}\x01", line.message)
+                } else {
+                    line.message.clone()
+                };
+                format!(
+                    ":{0}!{0}@replay PRIVMSG {channel} :{message}\r\n",
+                    line.source
+                )
+            })
+            .collect(),
+    );
+    irc_config.channels = vec![channel.clone()];
+    Ok(())
+}
+
+/// Wait (for up to a minute) for the tasks that the bot started, such as
+/// posting comments to github, to finish.
+async fn wait_for_tasks() {
+    let metrics = tokio::runtime::Handle::current().metrics();
+    let deadline = Instant::now() + Duration::from_secs(60);
+    while metrics.num_alive_tasks() > 0 && Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     env_logger::init();
-    let (irc_config, bot_config) = read_config();
-    let bot_config: &'static _ = Box::leak(Box::new(bot_config));
+    install_panic_hook();
+    let (mut irc_config, mut bot_config, replay) = read_config();
+    if let Some(replay @ Replay { lint: true, .. }) = &replay {
+        return lint(&bot_config, replay);
+    }
+    if let Some(ref replay) = replay {
+        prepare_replay(&mut irc_config, &mut bot_config, replay)?;
+    }
+    let bot_config = Arc::new(bot_config);
 
     // FIXME: Add a way to ask the bot to reboot itself?
 
-    let mut irc_state = IRCState::new(GithubType::RealGithubConnection);
+    let mut irc_state = IRCState::new(GithubType::RealGithubConnection, &bot_config);
+    if replay.is_none() {
+        irc_state.start_http_server(&bot_config);
+        // Join the channels we were invited to before we restarted, too.
+        irc_config.channels.extend(bot_config.invited_channels());
+    }
 
-    let irc_client: &'static mut _ = Box::leak(Box::new(IrcClient::from_config(irc_config).await?));
+    let mut irc_client = IrcClient::from_config(irc_config).await?;
     irc_client.identify()?;
 
     let mut irc_stream = irc_client.stream()?;
+    let irc_client = Arc::new(irc_client);
+    irc_state.watch_for_panics(&irc_client, &bot_config);
+    if replay.is_none() {
+        irc_state.start_agenda_reminders(&irc_client, &bot_config);
+    }
 
     while let Some(message) = irc_stream.next().await.transpose()? {
-        process_irc_message(irc_client, &mut irc_state, bot_config, message);
+        // If handling the message panics, keep reading (and so sending) until
+        // the task from watch_for_panics has reported it and quit.
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| {
+            process_irc_message(&irc_client, &mut irc_state, &bot_config, message)
+        }));
+    }
+
+    if replay.is_some() {
+        // Keep |irc_stream| around so the bot can still send its responses.
+        wait_for_tasks().await;
     }
 
     Ok(())
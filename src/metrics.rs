@@ -0,0 +1,159 @@
+//! Counts of the requests that the bot makes to the github API, and how long
+//! they take, so that we can tell whether slow responses from the bot are
+//! because github is slow.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What we know about the requests to one github API endpoint.
+#[derive(Default)]
+struct EndpointMetrics {
+    successes: u64,
+    failures: u64,
+    /// The total time that the requests took.
+    total: Duration,
+    /// The time that the slowest request took.
+    slowest: Duration,
+}
+
+/// What we know about the requests to each github API endpoint (named after
+/// the octorust method, such as "issues.create_comment").
+#[derive(Default)]
+pub(crate) struct GithubMetrics {
+    endpoints: Mutex<BTreeMap<&'static str, EndpointMetrics>>,
+}
+
+impl GithubMetrics {
+    /// Make the |request| to |endpoint|, recording how long it took and
+    /// whether it succeeded.
+    pub(crate) async fn time<T, E>(
+        &self,
+        endpoint: &'static str,
+        request: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = request.await;
+        self.record(endpoint, start.elapsed(), result.is_ok());
+        result
+    }
+
+    fn record(&self, endpoint: &'static str, elapsed: Duration, succeeded: bool) {
+        let mut endpoints = self.endpoints.lock().unwrap();
+        let metrics = endpoints.entry(endpoint).or_default();
+        if succeeded {
+            metrics.successes += 1;
+        } else {
+            metrics.failures += 1;
+        }
+        metrics.total += elapsed;
+        metrics.slowest = metrics.slowest.max(elapsed);
+    }
+
+    /// A line summarizing the requests to all endpoints, for the status
+    /// command, if we've made any.
+    pub(crate) fn summary(&self) -> Option<String> {
+        let endpoints = self.endpoints.lock().unwrap();
+        let requests = endpoints
+            .values()
+            .map(|metrics| metrics.successes + metrics.failures)
+            .sum::<u64>();
+        if requests == 0 {
+            return None;
+        }
+        let failures = endpoints
+            .values()
+            .map(|metrics| metrics.failures)
+            .sum::<u64>();
+        let total = endpoints
+            .values()
+            .map(|metrics| metrics.total)
+            .sum::<Duration>();
+        let slowest = endpoints
+            .values()
+            .map(|metrics| metrics.slowest)
+            .max()
+            .unwrap_or_default();
+        Some(format!(
+            "GitHub API: {}, {failures} failed, averaging {:.1} seconds (slowest {:.1} \
+             seconds)",
+            crate::count_of(requests as usize, "request"),
+            total.as_secs_f64() / requests as f64,
+            slowest.as_secs_f64()
+        ))
+    }
+
+    /// The metrics for each endpoint, in the Prometheus text format.
+    pub(crate) fn prometheus(&self) -> String {
+        let endpoints = self.endpoints.lock().unwrap();
+        let mut text = String::from(
+            "# HELP github_requests_total Requests made to the GitHub API.\n\
+             # TYPE github_requests_total counter\n",
+        );
+        for (endpoint, metrics) in endpoints.iter() {
+            for (outcome, count) in [
+                ("success", metrics.successes),
+                ("failure", metrics.failures),
+            ] {
+                let _ = writeln!(
+                    text,
+                    "github_requests_total{{endpoint=\"{endpoint}\",outcome=\"{outcome}\"}} {count}"
+                );
+            }
+        }
+        text.push_str(
+            "# HELP github_request_seconds_total Time spent waiting for the GitHub API.\n\
+             # TYPE github_request_seconds_total counter\n",
+        );
+        for (endpoint, metrics) in endpoints.iter() {
+            let _ = writeln!(
+                text,
+                "github_request_seconds_total{{endpoint=\"{endpoint}\"}} {}",
+                metrics.total.as_secs_f64()
+            );
+        }
+        text.push_str(
+            "# HELP github_request_seconds_max The slowest request to the GitHub API.\n\
+             # TYPE github_request_seconds_max gauge\n",
+        );
+        for (endpoint, metrics) in endpoints.iter() {
+            let _ = writeln!(
+                text,
+                "github_request_seconds_max{{endpoint=\"{endpoint}\"}} {}",
+                metrics.slowest.as_secs_f64()
+            );
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics() {
+        let metrics = GithubMetrics::default();
+        assert_eq!(metrics.summary(), None);
+        metrics.record("issues.get", Duration::from_millis(200), true);
+        metrics.record("issues.get", Duration::from_millis(400), false);
+        metrics.record("issues.create_comment", Duration::from_millis(1500), true);
+        assert_eq!(
+            metrics.summary().unwrap(),
+            "GitHub API: 3 requests, 1 failed, averaging 0.7 seconds (slowest 1.5 seconds)"
+        );
+        let text = metrics.prometheus();
+        assert!(
+            text.contains("github_requests_total{endpoint=\"issues.get\",outcome=\"success\"} 1\n")
+        );
+        assert!(
+            text.contains("github_requests_total{endpoint=\"issues.get\",outcome=\"failure\"} 1\n")
+        );
+        assert!(text.contains("github_request_seconds_total{endpoint=\"issues.get\"} 0.6"));
+        assert!(
+            text.contains("github_request_seconds_max{endpoint=\"issues.create_comment\"} 1.5\n")
+        );
+    }
+}
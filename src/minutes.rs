@@ -0,0 +1,317 @@
+//! W3C-style HTML minutes of a meeting, like the ones that RRSAgent makes,
+//! for channels where RRSAgent wasn't around to make them, and markdown
+//! minutes for groups that keep their minutes in a github repository.
+
+use crate::{escape_as_code_span, escape_for_html_block, format_log, strip_ci_prefix, ChannelLine};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Escape |s| for use as HTML text or in an attribute value.
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether |message| records a resolution, which the minutes summarize.
+pub(crate) fn is_resolution(message: &str) -> bool {
+    message.starts_with("RESOLUTION") || message.starts_with("RESOLVED")
+}
+
+/// The nicks that |lines| say are scribing, with "scribe: nick",
+/// "scribenick: nick", or "scribe+" (for the person who said it, or the
+/// people listed after it).
+pub(crate) fn scribes(lines: &[ChannelLine]) -> BTreeSet<String> {
+    let mut scribes = BTreeSet::new();
+    for line in lines.iter().filter(|line| !line.is_action) {
+        if let Some(nick) = strip_ci_prefix(&line.message, "scribenick:")
+            .or_else(|| strip_ci_prefix(&line.message, "scribe:"))
+        {
+            let _ = scribes.insert(String::from(nick.trim()));
+        } else if let Some(nicks) = strip_ci_prefix(&line.message, "scribe+") {
+            if nicks.trim().is_empty() {
+                let _ = scribes.insert(line.source.clone());
+            }
+            for nick in nicks.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+                let _ = scribes.insert(String::from(nick));
+            }
+        }
+    }
+    scribes
+}
+
+/// The names in |names| as a list for the minutes.
+fn name_list(names: &BTreeSet<String>) -> String {
+    if names.is_empty() {
+        String::from("-")
+    } else {
+        escape_html(&names.iter().cloned().collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// Make the HTML minutes of a meeting of |group| on |date|, from the
+/// |lines| said in the channel and who was |present| and sent |regrets|.
+pub(crate) fn format_minutes(
+    group: &str,
+    date: &str,
+    lines: &[ChannelLine],
+    present: &BTreeSet<String>,
+    regrets: &BTreeSet<String>,
+) -> String {
+    let mut topics = Vec::new();
+    let mut resolutions = Vec::new();
+    let mut body = String::new();
+    for line in lines {
+        let message = &line.message;
+        let topic = if line.is_action {
+            None
+        } else {
+            strip_ci_prefix(message, "topic:").or_else(|| strip_ci_prefix(message, "subtopic:"))
+        };
+        if let Some(topic) = topic {
+            if !topics.is_empty() {
+                body.push_str("</section>\n");
+            }
+            topics.push(topic);
+            let id = format!("t{:02}", topics.len());
+            let _ = writeln!(
+                body,
+                "<section id=\"{id}\">\n<h3>{}</h3>",
+                escape_html(topics.last().unwrap())
+            );
+            continue;
+        }
+        let source = escape_html(&line.source);
+        let text = escape_html(message);
+        if line.is_action {
+            let _ = writeln!(body, "<p class=\"irc\">* <cite>{source}</cite> {text}</p>");
+        } else if is_resolution(message) {
+            resolutions.push(message.clone());
+            let id = format!("r{:02}", resolutions.len());
+            let _ = writeln!(
+                body,
+                "<p class=\"resolution\" id=\"{id}\"><strong>{text}</strong></p>"
+            );
+        } else {
+            let _ = writeln!(
+                body,
+                "<p class=\"irc\">&lt;<cite>{source}</cite>&gt; {text}</p>"
+            );
+        }
+    }
+    if !topics.is_empty() {
+        body.push_str("</section>\n");
+    }
+
+    let group = escape_html(group);
+    let mut minutes = String::new();
+    let _ = write!(
+        minutes,
+        "<!DOCTYPE html>\n\
+         <html lang=\"en\">\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{group} &ndash; {date}</title>\n\
+         </head>\n\
+         <body>\n\
+         <header>\n\
+         <h1>{group}</h1>\n\
+         <h2>{date}</h2>\n\
+         </header>\n\
+         <div id=\"attendees\">\n\
+         <h2>Attendees</h2>\n\
+         <dl class=\"intro\">\n\
+         <dt>Present</dt><dd>{}</dd>\n\
+         <dt>Regrets</dt><dd>{}</dd>\n\
+         <dt>Scribe</dt><dd>{}</dd>\n\
+         </dl>\n\
+         </div>\n",
+        name_list(present),
+        name_list(regrets),
+        name_list(&scribes(lines)),
+    );
+    minutes.push_str("<nav id=\"toc\">\n<h2>Contents</h2>\n<ol>\n");
+    for (i, topic) in topics.iter().enumerate() {
+        let _ = writeln!(
+            minutes,
+            "<li><a href=\"#t{:02}\">{}</a></li>",
+            i + 1,
+            escape_html(topic)
+        );
+    }
+    minutes.push_str("</ol>\n</nav>\n<main id=\"meeting\">\n");
+    minutes.push_str(&body);
+    minutes.push_str("</main>\n");
+    if !resolutions.is_empty() {
+        minutes.push_str("<div id=\"ResolutionSummary\">\n<h2>Summary of resolutions</h2>\n<ol>\n");
+        for (i, resolution) in resolutions.iter().enumerate() {
+            let _ = writeln!(
+                minutes,
+                "<li><a href=\"#r{:02}\">{}</a></li>",
+                i + 1,
+                escape_html(resolution)
+            );
+        }
+        minutes.push_str("</ol>\n</div>\n");
+    }
+    minutes.push_str("</body>\n</html>\n");
+    minutes
+}
+
+/// Make the markdown minutes of a meeting of |group| on |date|, from the
+/// |lines| said in the channel and who was |present| and sent |regrets|,
+/// with the IRC log of each topic formatted as in our github comments.
+pub(crate) fn format_markdown_minutes(
+    group: &str,
+    date: &str,
+    lines: &[ChannelLine],
+    present: &BTreeSet<String>,
+    regrets: &BTreeSet<String>,
+) -> String {
+    // The lines before the first topic, and then the lines of each topic.
+    let mut sections = vec![(None, Vec::new())];
+    let mut resolutions = Vec::new();
+    for line in lines {
+        let message = &line.message;
+        if !line.is_action {
+            let topic = strip_ci_prefix(message, "topic:")
+                .or_else(|| strip_ci_prefix(message, "subtopic:"));
+            if topic.is_some() {
+                sections.push((topic, Vec::new()));
+                continue;
+            }
+            if is_resolution(message) {
+                resolutions.push(message.clone());
+            }
+        }
+        sections.last_mut().unwrap().1.push(line.clone());
+    }
+
+    let mut minutes = format!(
+        "# {} \u{2013} {date}\n\n\
+         * Present: {}\n\
+         * Regrets: {}\n\
+         * Scribe: {}\n",
+        escape_for_html_block(group),
+        name_list(present),
+        name_list(regrets),
+        name_list(&scribes(lines)),
+    );
+    for (topic, lines) in sections {
+        if let Some(topic) = topic {
+            let _ = write!(minutes, "\n## {}\n", escape_for_html_block(&topic));
+        }
+        if !lines.is_empty() {
+            // The <p> makes github treat the log as an HTML block, as the
+            // <details> does in our comments.
+            let _ = write!(minutes, "\n<p>\n{}</p>\n", format_log(&lines));
+        }
+    }
+    if !resolutions.is_empty() {
+        minutes.push_str("\n## Summary of resolutions\n\n");
+        for (i, resolution) in resolutions.iter().enumerate() {
+            let _ = writeln!(minutes, "{}. {}", i + 1, escape_as_code_span(resolution));
+        }
+    }
+    minutes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(source: &str, message: &str) -> ChannelLine {
+        ChannelLine {
+            source: String::from(source),
+            is_action: false,
+            message: String::from(message),
+        }
+    }
+
+    #[test]
+    fn test_format_minutes() {
+        let lines = [
+            line("fantasai", "scribenick: dbaron"),
+            line("dbaron", "Topic: <grid> gaps"),
+            line("dbaron", "fantasai: gaps & gutters are the same"),
+            line("dbaron", "RESOLVED: gaps are gutters"),
+            line("chair", "scribe+ fantasai"),
+            line("dbaron", "Topic: line-height"),
+            ChannelLine {
+                source: String::from("fantasai"),
+                is_action: true,
+                message: String::from("waves"),
+            },
+        ];
+        let present = ["chair", "dbaron", "fantasai"]
+            .iter()
+            .map(|name| String::from(*name))
+            .collect();
+        let minutes = format_minutes(
+            "CSS Working Group",
+            "2024-01-31",
+            &lines,
+            &present,
+            &BTreeSet::new(),
+        );
+        assert!(minutes.contains("<title>CSS Working Group &ndash; 2024-01-31</title>"));
+        assert!(minutes.contains("<dt>Present</dt><dd>chair, dbaron, fantasai</dd>"));
+        assert!(minutes.contains("<dt>Regrets</dt><dd>-</dd>"));
+        assert!(minutes.contains("<dt>Scribe</dt><dd>dbaron, fantasai</dd>"));
+        assert!(minutes.contains("<li><a href=\"#t01\">&lt;grid&gt; gaps</a></li>"));
+        assert!(minutes.contains("<section id=\"t02\">\n<h3>line-height</h3>"));
+        assert!(minutes.contains(
+            "<p class=\"irc\">&lt;<cite>dbaron</cite>&gt; fantasai: gaps &amp; gutters are the same</p>"
+        ));
+        assert!(minutes.contains(
+            "<p class=\"resolution\" id=\"r01\"><strong>RESOLVED: gaps are gutters</strong></p>"
+        ));
+        assert!(minutes.contains("<li><a href=\"#r01\">RESOLVED: gaps are gutters</a></li>"));
+        assert!(minutes.contains("<p class=\"irc\">* <cite>fantasai</cite> waves</p>"));
+    }
+
+    #[test]
+    fn test_format_markdown_minutes() {
+        let lines = [
+            line("fantasai", "scribenick: dbaron"),
+            line("dbaron", "Topic: <grid> gaps"),
+            line("dbaron", "fantasai: gaps & gutters are the same"),
+            line("dbaron", "RESOLVED: gaps are gutters"),
+        ];
+        let present = ["dbaron", "fantasai"]
+            .iter()
+            .map(|name| String::from(*name))
+            .collect();
+        let minutes = format_markdown_minutes(
+            "CSS Working Group",
+            "2024-01-31",
+            &lines,
+            &present,
+            &BTreeSet::new(),
+        );
+        assert_eq!(
+            minutes,
+            "# CSS Working Group \u{2013} 2024-01-31\n\
+             \n\
+             * Present: dbaron, fantasai\n\
+             * Regrets: -\n\
+             * Scribe: dbaron\n\
+             \n\
+             <p>\n\
+             &lt;fantasai> scribenick: dbaron<br>\n\
+             </p>\n\
+             \n\
+             ## &lt;grid> gaps\n\
+             \n\
+             <p>\n\
+             &lt;dbaron> fantasai: gaps &amp; gutters are the same<br>\n\
+             &lt;dbaron> RESOLVED: gaps are gutters<br>\n\
+             </p>\n\
+             \n\
+             ## Summary of resolutions\n\
+             \n\
+             1. `RESOLVED: gaps are gutters`\n"
+        );
+    }
+}
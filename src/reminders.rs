@@ -0,0 +1,91 @@
+//! Reminders of the agenda shortly before each of a channel's regular
+//! meetings, so that the chairs don't have to post it themselves and people
+//! can add what's missing before the meeting starts.
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+
+/// When one of a channel's regular meetings starts each week, in UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct MeetingTime {
+    weekday: Weekday,
+    time: NaiveTime,
+}
+
+impl MeetingTime {
+    /// Parse a meeting time such as "Wed 16:00" or "wednesday 16:00".
+    pub(crate) fn parse(s: &str) -> Option<MeetingTime> {
+        let (weekday, time) = s.trim().split_once(char::is_whitespace)?;
+        Some(MeetingTime {
+            weekday: weekday.parse().ok()?,
+            time: NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?,
+        })
+    }
+}
+
+/// The first time after |now| that is |minutes_before| minutes before one
+/// of |meeting_times|.
+pub(crate) fn next_reminder(
+    meeting_times: &[MeetingTime],
+    minutes_before: u64,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let before = Duration::minutes(i64::try_from(minutes_before).ok()?);
+    let today = now.date_naive();
+    // The reminder can be days before the meeting, so look at the meetings
+    // until a week after the latest reminder that could be today.
+    let last_day = 7 + before.num_days() + 1;
+    (0..=last_day)
+        .flat_map(|days| {
+            let date = today + Duration::days(days);
+            meeting_times
+                .iter()
+                .filter(move |meeting_time| meeting_time.weekday == date.weekday())
+                .map(move |meeting_time| date.and_time(meeting_time.time).and_utc() - before)
+        })
+        .filter(|reminder| *reminder > now)
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_reminder() {
+        assert_eq!(MeetingTime::parse("16:00"), None);
+        assert_eq!(MeetingTime::parse("Someday 16:00"), None);
+        assert_eq!(MeetingTime::parse("Wed 25:00"), None);
+        let times = ["Wed 16:00", "monday 00:05"]
+            .iter()
+            .map(|s| MeetingTime::parse(s).unwrap())
+            .collect::<Vec<_>>();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+
+        // 2024-01-31 is a Wednesday.
+        assert_eq!(
+            next_reminder(&times, 10, at("2024-01-31T12:00:00Z")),
+            Some(at("2024-01-31T15:50:00Z"))
+        );
+        assert_eq!(
+            next_reminder(&times, 10, at("2024-01-31T15:50:00Z")),
+            Some(at("2024-02-04T23:55:00Z"))
+        );
+        assert_eq!(
+            next_reminder(&times[..1], 10, at("2024-01-31T15:55:00Z")),
+            Some(at("2024-02-07T15:50:00Z"))
+        );
+        assert_eq!(
+            next_reminder(&times, 10, at("2024-02-04T23:56:00Z")),
+            Some(at("2024-02-07T15:50:00Z"))
+        );
+        assert_eq!(
+            next_reminder(&times[1..], 10, at("2024-02-04T23:56:00Z")),
+            Some(at("2024-02-11T23:55:00Z"))
+        );
+        assert_eq!(
+            next_reminder(&times[..1], 3 * 24 * 60, at("2024-01-31T12:00:00Z")),
+            Some(at("2024-02-04T16:00:00Z"))
+        );
+        assert_eq!(next_reminder(&[], 10, at("2024-01-31T12:00:00Z")), None);
+    }
+}
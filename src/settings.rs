@@ -0,0 +1,223 @@
+//! Changes to a channel's configuration that its operators make while the
+//! bot is running (such as which repositories it can comment on), which
+//! override the configuration file, and which we keep in the state database
+//! so that they survive restarts.  This includes the channels that aren't
+//! in the configuration file that the bot was invited to, if it has a
+//! default channel policy.
+
+use crate::store::Store;
+use crate::{BotConfig, ChannelConfig};
+use serde::{Deserialize, Serialize};
+
+/// The settings of a channel that have been changed from its configuration.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ChannelSettings {
+    #[serde(default)]
+    github_repos_allowed: Option<Vec<String>>,
+    #[serde(default)]
+    publish_resolutions_only: Option<bool>,
+    // Whether the channel isn't in the configuration, but we joined it
+    // when invited because of the default channel policy.
+    #[serde(default)]
+    invited: bool,
+}
+
+impl BotConfig {
+    /// The configuration of |channel|, if it's in the configuration file or
+    /// we were invited to it under the default channel policy.
+    pub(crate) fn channel_config(&self, channel: &str) -> Option<&ChannelConfig> {
+        self.channels.get(channel).or_else(|| {
+            let settings = self.channel_settings.read().unwrap();
+            settings
+                .get(channel)
+                .is_some_and(|settings| settings.invited)
+                .then_some(&self.default_channel_config)
+        })
+    }
+
+    /// The name of the group that uses |channel|: the one in its
+    /// configuration, or the channel's name, if it isn't configured.
+    pub(crate) fn group(&self, channel: &str) -> String {
+        match self.channels.get(channel) {
+            Some(channel_config) => channel_config.group.clone(),
+            None => String::from(channel),
+        }
+    }
+
+    /// The channels that aren't in the configuration file that we were
+    /// invited to under the default channel policy, so that we can join
+    /// them again when we restart.
+    pub fn invited_channels(&self) -> Vec<String> {
+        let settings = self.channel_settings.read().unwrap();
+        settings
+            .iter()
+            .filter(|(channel, settings)| settings.invited && !self.channels.contains_key(*channel))
+            .map(|(channel, _)| channel.clone())
+            .collect()
+    }
+
+    /// The repositories that the bot can comment on in |channel|.
+    pub(crate) fn github_repos_allowed(&self, channel: &str) -> Vec<String> {
+        let settings = self.channel_settings.read().unwrap();
+        match settings
+            .get(channel)
+            .and_then(|settings| settings.github_repos_allowed.as_ref())
+        {
+            Some(repos) => repos.clone(),
+            None => self
+                .channels
+                .get(channel)
+                .map(|channel_config| channel_config.github_repos_allowed.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Whether the comments for |channel| should be resolutions only.
+    pub(crate) fn publish_resolutions_only(&self, channel: &str) -> bool {
+        let settings = self.channel_settings.read().unwrap();
+        match settings
+            .get(channel)
+            .and_then(|settings| settings.publish_resolutions_only)
+        {
+            Some(publish_resolutions_only) => publish_resolutions_only,
+            None => self
+                .channels
+                .get(channel)
+                .is_some_and(|channel_config| channel_config.publish_resolutions_only),
+        }
+    }
+}
+
+/// Replace the settings of every channel with the ones in |store|.
+pub(crate) fn load(config: &BotConfig, store: &Store) {
+    let mut settings = config.channel_settings.write().unwrap();
+    settings.clear();
+    for channel in store.keys("channel_settings") {
+        if let Some(channel_settings) = store.get("channel_settings", &channel) {
+            let _ = settings.insert(channel, channel_settings);
+        }
+    }
+}
+
+/// Change the settings of |channel| with |update|, and store them.
+fn change(
+    config: &BotConfig,
+    store: &Store,
+    channel: &str,
+    update: impl FnOnce(&mut ChannelSettings),
+) {
+    let mut settings = config.channel_settings.write().unwrap();
+    let channel_settings = settings.entry(String::from(channel)).or_default();
+    update(channel_settings);
+    store.put("channel_settings", channel, &*channel_settings);
+}
+
+/// Start treating |channel|, which isn't in the configuration, as one of the
+/// bot's channels (with no repositories allowed), because someone invited
+/// the bot to it.
+pub(crate) fn add_invited_channel(config: &BotConfig, store: &Store, channel: &str) {
+    change(config, store, channel, |settings| settings.invited = true);
+}
+
+/// Allow the bot to comment on issues in |repo| in |channel|, returning
+/// whether it wasn't already allowed to.
+pub(crate) fn allow_repo(config: &BotConfig, store: &Store, channel: &str, repo: &str) -> bool {
+    let mut repos = config.github_repos_allowed(channel);
+    if repos
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(repo))
+    {
+        return false;
+    }
+    repos.push(String::from(repo));
+    change(config, store, channel, |settings| {
+        settings.github_repos_allowed = Some(repos)
+    });
+    true
+}
+
+/// Stop the bot from commenting on issues in |repo| in |channel|, returning
+/// whether it was allowed to.
+pub(crate) fn disallow_repo(config: &BotConfig, store: &Store, channel: &str, repo: &str) -> bool {
+    let mut repos = config.github_repos_allowed(channel);
+    let count = repos.len();
+    repos.retain(|allowed| !allowed.eq_ignore_ascii_case(repo));
+    if repos.len() == count {
+        return false;
+    }
+    change(config, store, channel, |settings| {
+        settings.github_repos_allowed = Some(repos)
+    });
+    true
+}
+
+/// Set whether the comments for |channel| should be resolutions only.
+pub(crate) fn set_publish_resolutions_only(
+    config: &BotConfig,
+    store: &Store,
+    channel: &str,
+    publish_resolutions_only: bool,
+) {
+    change(config, store, channel, |settings| {
+        settings.publish_resolutions_only = Some(publish_resolutions_only)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChannelConfig;
+
+    #[test]
+    fn test_channel_settings() {
+        let config = BotConfig {
+            channels: [(
+                String::from("#css"),
+                ChannelConfig {
+                    github_repos_allowed: vec![String::from("w3c/csswg-drafts")],
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let store = Store::open(None).unwrap();
+
+        assert!(allow_repo(&config, &store, "#css", "w3c/fxtf-drafts"));
+        assert!(!allow_repo(&config, &store, "#css", "W3C/fxtf-drafts"));
+        assert!(disallow_repo(&config, &store, "#css", "w3c/csswg-drafts"));
+        assert!(!disallow_repo(&config, &store, "#css", "w3c/csswg-drafts"));
+        set_publish_resolutions_only(&config, &store, "#css", true);
+        assert_eq!(config.github_repos_allowed("#css"), ["w3c/fxtf-drafts"]);
+        assert!(config.publish_resolutions_only("#css"));
+
+        // The settings come back from the store, and replace any others.
+        let _ = config.channel_settings.write().unwrap().remove("#css");
+        assert_eq!(config.github_repos_allowed("#css"), ["w3c/csswg-drafts"]);
+        load(&config, &store);
+        assert_eq!(config.github_repos_allowed("#css"), ["w3c/fxtf-drafts"]);
+        assert!(config.publish_resolutions_only("#css"));
+        load(&config, &Store::open(None).unwrap());
+        assert!(!config.publish_resolutions_only("#css"));
+    }
+
+    #[test]
+    fn test_invited_channel() {
+        let config = BotConfig::default();
+        let store = Store::open(None).unwrap();
+        assert!(config.channel_config("#fx").is_none());
+        add_invited_channel(&config, &store, "#fx");
+        assert!(config.channel_config("#fx").is_some());
+        assert_eq!(config.group("#fx"), "#fx");
+        assert!(config.github_repos_allowed("#fx").is_empty());
+        assert!(allow_repo(&config, &store, "#fx", "w3c/fxtf-drafts"));
+
+        // We remember the channel after restarting.
+        load(&config, &Store::open(None).unwrap());
+        assert!(config.channel_config("#fx").is_none());
+        load(&config, &store);
+        assert_eq!(config.invited_channels(), ["#fx"]);
+        assert_eq!(config.github_repos_allowed("#fx"), ["w3c/fxtf-drafts"]);
+    }
+}
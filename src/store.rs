@@ -0,0 +1,373 @@
+//! Storage of the bot's state in an SQLite database, so that it can survive
+//! restarts of the bot.  Without a database file configured, the same
+//! storage is kept in memory instead.
+
+use log::warn;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A resolution that we posted to github.
+pub(crate) struct RecordedResolution {
+    pub(crate) id: i64,
+    pub(crate) github_url: String,
+    /// The comment we posted it in, which we didn't record for resolutions
+    /// that we posted before we started recording comments.
+    pub(crate) comment_url: Option<String>,
+    pub(crate) resolution: String,
+    /// When we posted it, in seconds since the epoch.
+    pub(crate) time: i64,
+}
+
+/// Lowercase the github URLs of the resolutions recorded before we started
+/// lowercasing the owners and repositories in github URLs (which github
+/// treats case-insensitively), so that they're found by the URLs we use
+/// now.
+fn lowercase_github_urls(connection: &Connection) -> rusqlite::Result<usize> {
+    connection.execute(
+        "UPDATE resolutions SET github_url = lower(github_url) \
+         WHERE github_url LIKE 'https://github.com/%' AND github_url != lower(github_url)",
+        [],
+    )
+}
+
+/// The bot's storage, which the parts of the bot that need to remember things
+/// (such as the current topic in each channel) share.
+pub(crate) struct Store {
+    connection: Mutex<Connection>,
+}
+
+impl Store {
+    /// Open the database at |path|, or an in-memory database if there is
+    /// no path, and create the tables if they're not already there.
+    pub(crate) fn open(path: Option<&str>) -> rusqlite::Result<Store> {
+        let connection = match path {
+            Some(path) => Connection::open(path)?,
+            None => Connection::open_in_memory()?,
+        };
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS state (
+                 namespace TEXT NOT NULL,
+                 key TEXT NOT NULL,
+                 value TEXT NOT NULL,
+                 PRIMARY KEY (namespace, key)
+             );
+             CREATE TABLE IF NOT EXISTS resolutions (
+                 id INTEGER PRIMARY KEY,
+                 channel TEXT NOT NULL,
+                 github_url TEXT NOT NULL,
+                 resolution TEXT NOT NULL,
+                 time INTEGER NOT NULL,
+                 comment_url TEXT
+             );
+             CREATE INDEX IF NOT EXISTS resolutions_by_url ON resolutions (github_url);",
+        )?;
+        // Databases made before we recorded the URLs of the comments don't
+        // have that column yet.
+        if connection
+            .prepare("SELECT comment_url FROM resolutions LIMIT 0")
+            .is_err()
+        {
+            connection.execute_batch("ALTER TABLE resolutions ADD COLUMN comment_url TEXT")?;
+        }
+        lowercase_github_urls(&connection)?;
+        Ok(Store {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    /// Get the value stored for |key| in |namespace| (such as the current
+    /// topic in a channel), if there is one.
+    pub(crate) fn get<T: DeserializeOwned>(&self, namespace: &str, key: &str) -> Option<T> {
+        let result = self
+            .connection
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value FROM state WHERE namespace = ?1 AND key = ?2",
+                params![namespace, key],
+                |row| row.get::<_, String>(0),
+            )
+            .optional();
+        match result {
+            Ok(None) => None,
+            Ok(Some(json)) => match serde_json::from_str(&json) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    warn!("Unable to parse stored {namespace} for {key}: {err}");
+                    None
+                }
+            },
+            Err(err) => {
+                warn!("Unable to read stored {namespace} for {key}: {err}");
+                None
+            }
+        }
+    }
+
+    /// Store |value| for |key| in |namespace|, replacing what was there.
+    pub(crate) fn put<T: Serialize>(&self, namespace: &str, key: &str, value: &T) {
+        let json = serde_json::to_string(value).unwrap();
+        if let Err(err) = self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO state (namespace, key, value) VALUES (?1, ?2, ?3)",
+            params![namespace, key, json],
+        ) {
+            warn!("Unable to store {namespace} for {key}: {err}");
+        }
+    }
+
+    /// The keys (such as channels) that have something stored in
+    /// |namespace|.
+    pub(crate) fn keys(&self, namespace: &str) -> Vec<String> {
+        let connection = self.connection.lock().unwrap();
+        let result = connection
+            .prepare("SELECT key FROM state WHERE namespace = ?1 ORDER BY key")
+            .and_then(|mut statement| {
+                let rows = statement.query_map(params![namespace], |row| row.get(0))?;
+                rows.collect()
+            });
+        match result {
+            Ok(keys) => keys,
+            Err(err) => {
+                warn!("Unable to read the keys of stored {namespace}: {err}");
+                vec![]
+            }
+        }
+    }
+
+    /// Remove what is stored for |key| in |namespace|.
+    pub(crate) fn remove(&self, namespace: &str, key: &str) {
+        if let Err(err) = self.connection.lock().unwrap().execute(
+            "DELETE FROM state WHERE namespace = ?1 AND key = ?2",
+            params![namespace, key],
+        ) {
+            warn!("Unable to remove stored {namespace} for {key}: {err}");
+        }
+    }
+
+    /// Remove everything stored for |channel| (including the resolutions we
+    /// posted from it), except the changes to its configuration.
+    pub(crate) fn forget_channel(&self, channel: &str) {
+        let connection = self.connection.lock().unwrap();
+        for statement in [
+            "DELETE FROM state WHERE key = ?1 AND namespace != 'channel_settings'",
+            "DELETE FROM resolutions WHERE channel = ?1",
+        ] {
+            if let Err(err) = connection.execute(statement, params![channel]) {
+                warn!("Unable to forget {channel}: {err}");
+            }
+        }
+    }
+
+    /// The time (in seconds since the epoch) that we last posted a
+    /// resolution (rather than a summary or action) to |github_url|, if we
+    /// have done so since |since|.
+    pub(crate) fn last_resolution_time(&self, github_url: &str, since: i64) -> Option<i64> {
+        let result = self.connection.lock().unwrap().query_row(
+            "SELECT MAX(time) FROM resolutions \
+             WHERE github_url = ?1 AND time >= ?2 \
+             AND (resolution LIKE 'RESOLUTION%' OR resolution LIKE 'RESOLVED%')",
+            params![github_url, since],
+            |row| row.get::<_, Option<i64>>(0),
+        );
+        match result {
+            Ok(time) => time,
+            Err(err) => {
+                warn!("Unable to read resolutions on {github_url}: {err}");
+                None
+            }
+        }
+    }
+
+    /// The last |limit| resolutions (rather than summaries or actions) that
+    /// we posted for |channel|, newest first.
+    pub(crate) fn recent_resolutions(&self, channel: &str, limit: u32) -> Vec<RecordedResolution> {
+        let connection = self.connection.lock().unwrap();
+        let result = connection
+            .prepare(
+                "SELECT id, github_url, comment_url, resolution, time FROM resolutions \
+                 WHERE channel = ?1 \
+                 AND (resolution LIKE 'RESOLUTION%' OR resolution LIKE 'RESOLVED%') \
+                 ORDER BY id DESC LIMIT ?2",
+            )
+            .and_then(|mut statement| {
+                let rows = statement.query_map(params![channel, limit], |row| {
+                    Ok(RecordedResolution {
+                        id: row.get(0)?,
+                        github_url: row.get(1)?,
+                        comment_url: row.get(2)?,
+                        resolution: row.get(3)?,
+                        time: row.get(4)?,
+                    })
+                })?;
+                rows.collect()
+            });
+        match result {
+            Ok(resolutions) => resolutions,
+            Err(err) => {
+                warn!("Unable to read resolutions for {channel}: {err}");
+                vec![]
+            }
+        }
+    }
+
+    /// Record that we posted |resolutions| to |github_url|, in the comment
+    /// at |comment_url| (if we posted a comment, rather than just following
+    /// the discussion).
+    pub(crate) fn add_resolutions(
+        &self,
+        channel: &str,
+        github_url: &str,
+        comment_url: Option<&str>,
+        resolutions: &[String],
+    ) {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs() as i64);
+        let connection = self.connection.lock().unwrap();
+        for resolution in resolutions {
+            if let Err(err) = connection.execute(
+                "INSERT INTO resolutions (channel, github_url, resolution, time, comment_url) \
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![channel, github_url, resolution, time, comment_url],
+            ) {
+                warn!("Unable to record resolution on {github_url}: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state() {
+        let store = Store::open(None).unwrap();
+        assert_eq!(store.get::<Vec<String>>("agenda", "#css"), None);
+        store.put("agenda", "#css", &vec!["one", "two"]);
+        store.put("agenda", "#fx", &vec!["three"]);
+        assert_eq!(
+            store.get::<Vec<String>>("agenda", "#css"),
+            Some(vec!["one".to_string(), "two".to_string()])
+        );
+        store.put("agenda", "#css", &vec!["four"]);
+        assert_eq!(
+            store.get::<Vec<String>>("agenda", "#css"),
+            Some(vec!["four".to_string()])
+        );
+        assert_eq!(store.get::<Vec<String>>("topic", "#css"), None);
+        store.remove("agenda", "#css");
+        assert_eq!(store.get::<Vec<String>>("agenda", "#css"), None);
+        assert_eq!(
+            store.get::<Vec<String>>("agenda", "#fx"),
+            Some(vec!["three".to_string()])
+        );
+
+        store.put("agenda", "#css", &vec!["five"]);
+        store.put("channel_settings", "#css", &vec!["w3c/csswg-drafts"]);
+        store.add_resolutions(
+            "#css",
+            "https://github.com/w3c/csswg-drafts/issues/1",
+            None,
+            &["RESOLVED: one".to_string()],
+        );
+        store.forget_channel("#css");
+        assert_eq!(store.get::<Vec<String>>("agenda", "#css"), None);
+        assert!(store.recent_resolutions("#css", 10).is_empty());
+        assert_eq!(
+            store.get::<Vec<String>>("channel_settings", "#css"),
+            Some(vec!["w3c/csswg-drafts".to_string()])
+        );
+        assert_eq!(
+            store.get::<Vec<String>>("agenda", "#fx"),
+            Some(vec!["three".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_resolutions() {
+        let store = Store::open(None).unwrap();
+        let url = "https://github.com/w3c/csswg-drafts/issues/1";
+        store.add_resolutions(
+            "#css",
+            url,
+            Some(&format!("{url}#issuecomment-1")),
+            &["RESOLVED: one".to_string(), "RESOLVED: two".to_string()],
+        );
+        store.add_resolutions(
+            "#css",
+            "https://github.com/w3c/csswg-drafts/issues/2",
+            Some("https://github.com/w3c/csswg-drafts/issues/2#issuecomment-2"),
+            &["RESOLVED: three".to_string()],
+        );
+        let connection = store.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare(
+                "SELECT channel, resolution, time FROM resolutions \
+                 WHERE github_url = ?1 ORDER BY id",
+            )
+            .unwrap();
+        let resolutions = statement
+            .query_map(params![url], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, i64>(2)?,
+                ))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(resolutions.len(), 2);
+        assert_eq!(resolutions[0].1, "RESOLVED: one");
+        assert_eq!(resolutions[1].1, "RESOLVED: two");
+        assert!(resolutions
+            .iter()
+            .all(|(channel, _, time)| channel == "#css" && *time > 0));
+        drop(statement);
+        drop(connection);
+
+        let time = resolutions[0].2;
+        assert_eq!(store.last_resolution_time(url, time), Some(time));
+        assert_eq!(store.last_resolution_time(url, time + 1), None);
+        let other_url = "https://github.com/w3c/csswg-drafts/issues/3";
+        store.add_resolutions(
+            "#css",
+            other_url,
+            Some(&format!("{other_url}#issuecomment-3")),
+            &["SUMMARY: not a resolution".to_string()],
+        );
+        assert_eq!(store.last_resolution_time(other_url, 0), None);
+
+        let recent = store.recent_resolutions("#css", 2);
+        assert_eq!(
+            recent
+                .iter()
+                .map(|r| r.resolution.as_str())
+                .collect::<Vec<_>>(),
+            vec!["RESOLVED: three", "RESOLVED: two"]
+        );
+        assert_eq!(
+            recent[1].comment_url.as_deref(),
+            Some("https://github.com/w3c/csswg-drafts/issues/1#issuecomment-1")
+        );
+        assert!(store.recent_resolutions("#fx", 10).is_empty());
+
+        store.add_resolutions(
+            "#css",
+            "https://github.com/W3C/csswg-drafts/issues/4",
+            None,
+            &["RESOLVED: four".to_string()],
+        );
+        assert_eq!(
+            lowercase_github_urls(&store.connection.lock().unwrap()).unwrap(),
+            1
+        );
+        assert!(store
+            .last_resolution_time("https://github.com/w3c/csswg-drafts/issues/4", 0)
+            .is_some());
+    }
+}
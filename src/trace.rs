@@ -0,0 +1,216 @@
+//! Optional export of traces, in the OpenTelemetry protocol (OTLP) over
+//! HTTP, that follow each topic from the lines said in it, through the end
+//! of the topic, to the comment we post for it, so that an observability
+//! backend can show where a comment that never showed up went wrong.
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::fmt::Write;
+use std::hash::{BuildHasher, Hasher};
+use std::iter;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The current time, in nanoseconds since the epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+/// A random ID of |bytes| bytes, in hex.
+pub(crate) fn random_id(bytes: usize) -> String {
+    let mut id = String::new();
+    while id.len() < bytes * 2 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(now());
+        let _ = write!(id, "{:016x}", hasher.finish());
+    }
+    id.truncate(bytes * 2);
+    id
+}
+
+/// Something that happened during a topic, such as a line being said.
+#[derive(Clone, Serialize, Deserialize)]
+struct TraceEvent {
+    name: String,
+    time: u64,
+    attributes: Vec<(String, String)>,
+}
+
+/// Our attempt to post the comment for a topic: when we started, and the
+/// error, if it failed.
+pub(crate) struct CommentAttempt {
+    pub(crate) started: u64,
+    pub(crate) error: Option<String>,
+}
+
+/// The trace of a topic, which goes along with the topic (through holding
+/// it until someone says to publish it, and the task that posts it) until
+/// we export it.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct TopicTrace {
+    trace_id: String,
+    span_id: String,
+    started: u64,
+    events: Vec<TraceEvent>,
+}
+
+impl TopicTrace {
+    /// Start the trace of a topic that starts now.
+    pub(crate) fn new() -> TopicTrace {
+        TopicTrace {
+            trace_id: random_id(16),
+            span_id: random_id(8),
+            started: now(),
+            events: vec![],
+        }
+    }
+
+    /// Record that |name| (such as "line received") happened now.
+    pub(crate) fn event(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.events.push(TraceEvent {
+            name: String::from(name),
+            time: now(),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| (String::from(*key), String::from(*value)))
+                .collect(),
+        });
+    }
+
+    /// The OTLP request that exports the trace of the topic |topic| in
+    /// |channel|, which ends now: a span for the topic and, if we tried to
+    /// post it, a child span for posting the |comment| to |github_url|.
+    pub(crate) fn to_otlp(
+        &self,
+        channel: &str,
+        topic: &str,
+        github_url: Option<&str>,
+        comment: Option<CommentAttempt>,
+    ) -> serde_json::Value {
+        let end = now();
+        let mut topic_attributes = vec![("irc.channel", channel), ("topic", topic)];
+        if let Some(github_url) = github_url {
+            topic_attributes.push(("github.url", github_url));
+        }
+        let events = self
+            .events
+            .iter()
+            .map(|event| {
+                serde_json::json!({
+                    "name": event.name,
+                    "timeUnixNano": event.time.to_string(),
+                    "attributes": otlp_attributes(
+                        event.attributes.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+                    ),
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut spans = vec![serde_json::json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "name": "topic",
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": self.started.to_string(),
+            "endTimeUnixNano": end.to_string(),
+            "attributes": otlp_attributes(topic_attributes.into_iter()),
+            "events": events,
+        })];
+        if let Some(comment) = comment {
+            let comment_attributes = github_url.map(|url| ("github.url", url));
+            spans.push(serde_json::json!({
+                "traceId": self.trace_id,
+                "spanId": random_id(8),
+                "parentSpanId": self.span_id,
+                "name": "post comment",
+                // SPAN_KIND_CLIENT
+                "kind": 3,
+                "startTimeUnixNano": comment.started.to_string(),
+                "endTimeUnixNano": end.to_string(),
+                "attributes": otlp_attributes(comment_attributes.into_iter()),
+                "status": match comment.error {
+                    // STATUS_CODE_OK
+                    None => serde_json::json!({ "code": 1 }),
+                    // STATUS_CODE_ERROR
+                    Some(error) => serde_json::json!({ "code": 2, "message": error }),
+                },
+            }));
+        }
+        let service = otlp_attributes(iter::once(("service.name", env!("CARGO_PKG_NAME"))));
+        serde_json::json!({
+            "resourceSpans": [{
+                "resource": { "attributes": service },
+                "scopeSpans": [{
+                    "scope": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+}
+
+/// OTLP attributes with the given string values.
+fn otlp_attributes<'a>(attributes: impl Iterator<Item = (&'a str, &'a str)>) -> serde_json::Value {
+    attributes
+        .map(|(key, value)| serde_json::json!({ "key": key, "value": { "stringValue": value } }))
+        .collect()
+}
+
+/// Send |request| to the OTLP collector at |endpoint| (such as
+/// http://localhost:4318).
+pub(crate) async fn export(endpoint: String, request: serde_json::Value) {
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let result = reqwest::Client::new()
+        .post(&url)
+        .json(&request)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+    if let Err(err) = result {
+        warn!("Unable to export trace to {url}: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_otlp() {
+        let mut trace = TopicTrace::new();
+        trace.event("line received", &[("irc.nick", "dbaron")]);
+        trace.event("topic ended", &[]);
+        let url = "https://github.com/w3c/csswg-drafts/issues/1";
+        let request = trace.to_otlp(
+            "#css",
+            "line-height",
+            Some(url),
+            Some(CommentAttempt {
+                started: now(),
+                error: Some(String::from("Simulated failure")),
+            }),
+        );
+        let spans = &request["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans.as_array().unwrap().len(), 2);
+        let (topic, comment) = (&spans[0], &spans[1]);
+        assert_eq!(topic["traceId"].as_str().unwrap().len(), 32);
+        assert_eq!(topic["spanId"].as_str().unwrap().len(), 16);
+        assert_eq!(topic["name"], "topic");
+        assert_eq!(topic["attributes"][2]["value"]["stringValue"], url);
+        assert_eq!(topic["events"][0]["name"], "line received");
+        assert_eq!(
+            topic["events"][0]["attributes"][0]["value"]["stringValue"],
+            "dbaron"
+        );
+        assert_eq!(comment["traceId"], topic["traceId"]);
+        assert_eq!(comment["parentSpanId"], topic["spanId"]);
+        assert_ne!(comment["spanId"], topic["spanId"]);
+        assert_eq!(comment["status"]["code"], 2);
+        assert_eq!(comment["status"]["message"], "Simulated failure");
+    }
+}
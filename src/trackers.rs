@@ -0,0 +1,197 @@
+//! The issue trackers other than github that a topic can be about (Bugzilla
+//! and JIRA), which go through the same topic pipeline as github issues:
+//! the tracker's URL for the issue is the topic's github_url, and when the
+//! topic ends, the discussion is posted to the issue as a comment in the
+//! tracker's own format instead of markdown.
+
+use crate::bugzilla::{self, BugURL};
+use crate::jira::{self, JiraURL};
+use crate::metrics::GithubMetrics;
+use crate::{BotConfig, ChannelConfig, GithubType, TopicData};
+use std::fmt::Write;
+
+/// An issue in an issue tracker other than github.
+pub(crate) enum TrackerIssue {
+    Bugzilla(BugURL),
+    Jira(JiraURL),
+}
+
+impl TrackerIssue {
+    /// Parse the URL of a Bugzilla bug or a JIRA issue.
+    pub(crate) fn from_string(s: &str) -> Option<TrackerIssue> {
+        BugURL::from_string(s)
+            .map(TrackerIssue::Bugzilla)
+            .or_else(|| JiraURL::from_string(s).map(TrackerIssue::Jira))
+    }
+
+    /// The canonical URL of the issue.
+    pub(crate) fn url(&self) -> &str {
+        match self {
+            TrackerIssue::Bugzilla(bug) => &bug.url,
+            TrackerIssue::Jira(issue) => &issue.url,
+        }
+    }
+
+    /// What the tracker calls an issue, such as "bug".
+    pub(crate) fn noun(&self) -> &'static str {
+        match self {
+            TrackerIssue::Bugzilla(_) => "bug",
+            TrackerIssue::Jira(_) => "issue",
+        }
+    }
+
+    /// The URL of the Bugzilla installation or JIRA site that the issue is
+    /// in.
+    pub(crate) fn base(&self) -> &str {
+        match self {
+            TrackerIssue::Bugzilla(bug) => &bug.base,
+            TrackerIssue::Jira(issue) => &issue.base,
+        }
+    }
+
+    /// Whether a channel with |channel_config| can comment on the issue.
+    pub(crate) fn is_allowed(&self, channel_config: &ChannelConfig) -> bool {
+        match self {
+            TrackerIssue::Bugzilla(bug) => bug.is_in(&channel_config.bugzilla_urls_allowed),
+            TrackerIssue::Jira(issue) => issue.is_in(&channel_config.jira_urls_allowed),
+        }
+    }
+
+    /// The title of the issue, or None if it doesn't exist (or isn't
+    /// visible to us).
+    pub(crate) async fn fetch_title(
+        &self,
+        config: &BotConfig,
+        github_type: GithubType,
+        metrics: &GithubMetrics,
+    ) -> Result<Option<String>, String> {
+        match self {
+            TrackerIssue::Bugzilla(bug) => {
+                bugzilla::fetch_bug_title(config, github_type, metrics, bug).await
+            }
+            TrackerIssue::Jira(issue) => {
+                jira::fetch_issue_title(config, github_type, metrics, issue).await
+            }
+        }
+    }
+
+    /// The text of the comment on the issue for the discussion in |data|.
+    pub(crate) fn format_comment(&self, data: &TopicData) -> String {
+        match self {
+            TrackerIssue::Bugzilla(_) => format_comment(data, "bug", false),
+            TrackerIssue::Jira(_) => format_comment(data, "issue", true),
+        }
+    }
+
+    /// Add a comment with |text| to the issue.
+    pub(crate) async fn post_comment(
+        &self,
+        config: &BotConfig,
+        github_type: GithubType,
+        metrics: &GithubMetrics,
+        text: String,
+    ) -> Result<(), String> {
+        match self {
+            TrackerIssue::Bugzilla(bug) => {
+                bugzilla::post_comment(config, github_type, metrics, bug, text).await
+            }
+            TrackerIssue::Jira(issue) => {
+                jira::post_comment(config, github_type, metrics, issue, text).await
+            }
+        }
+    }
+}
+
+/// The text of the comment on a |noun| (such as "bug") for the discussion in
+/// |data|, which is plain text, since these trackers don't render markdown,
+/// except that with |wiki_markup|, the resolutions and the log are in
+/// {noformat} blocks so that JIRA doesn't treat them as markup.
+fn format_comment(data: &TopicData, noun: &str, wiki_markup: bool) -> String {
+    let (block_start, block_end) = if wiki_markup {
+        ("{noformat}\n", "{noformat}\n")
+    } else {
+        ("", "")
+    };
+    let mut comment = String::new();
+    if let Some(header) = data.meeting_info.header() {
+        let _ = write!(comment, "{header}\n\n");
+    }
+    let _ = write!(comment, "The {} just discussed ", data.group);
+    if data.topic.is_empty() {
+        let _ = write!(comment, "this {noun}");
+    } else {
+        let _ = write!(comment, "\"{}\"", data.topic);
+    }
+    let (lines, resolutions) = if data.anonymize_nicks {
+        crate::anonymize(&data.lines, &data.resolutions, &data.nick_roles)
+    } else {
+        (data.lines.clone(), data.resolutions.clone())
+    };
+    if resolutions.is_empty() {
+        comment.push_str(".\n");
+    } else {
+        let _ = write!(comment, ", and agreed to the following:\n\n{block_start}");
+        for resolution in &resolutions {
+            let _ = writeln!(comment, "  {resolution}");
+        }
+        comment.push_str(block_end);
+    }
+    if !data.publish_resolutions_only {
+        let _ = write!(
+            comment,
+            "\nThe full IRC log of that discussion:\n{block_start}"
+        );
+        for line in &lines {
+            let _ = writeln!(comment, "{line}");
+        }
+        comment.push_str(block_end);
+    }
+    if let Some(ref log_archive_url) = data.log_archive_url {
+        let _ = write!(comment, "\nIRC log of this discussion: {log_archive_url}\n");
+    }
+    if let Some(ref minutes_url) = data.minutes_url {
+        let _ = write!(comment, "\nMinutes of the meeting: {minutes_url}\n");
+    }
+    comment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChannelLine;
+
+    #[test]
+    fn test_format_comment() {
+        let mut data = TopicData::new(
+            "line-height",
+            "CSS Working Group",
+            &ChannelConfig::default(),
+        );
+        let line = |source: &str, message: &str| ChannelLine {
+            source: String::from(source),
+            is_action: false,
+            message: String::from(message),
+        };
+        data.lines = vec![
+            line("dbaron", "Topic: line-height"),
+            line("fantasai", "RESOLVED: line-height is fine"),
+        ];
+        data.resolutions = vec![String::from("RESOLVED: line-height is fine")];
+        assert_eq!(
+            format_comment(&data, "bug", false),
+            "The CSS Working Group just discussed \"line-height\", and agreed to the \
+             following:\n\
+             \n  RESOLVED: line-height is fine\n\
+             \nThe full IRC log of that discussion:\n\
+             <dbaron> Topic: line-height\n\
+             <fantasai> RESOLVED: line-height is fine\n"
+        );
+        data.topic = String::new();
+        data.publish_resolutions_only = true;
+        assert_eq!(
+            format_comment(&data, "issue", true),
+            "The CSS Working Group just discussed this issue, and agreed to the following:\n\
+             \n{noformat}\n  RESOLVED: line-height is fine\n{noformat}\n"
+        );
+    }
+}
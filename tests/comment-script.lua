@@ -0,0 +1,10 @@
+-- The comment script for #testcommentscript in the chat tests.  It doesn't
+-- comment on topics with "secret" in their name, and adds a line to the end
+-- of other comments.
+function format_comment(comment)
+  if string.find(comment.topic, "secret") then
+    return nil
+  end
+  return comment.body .. "\n" .. #comment.resolutions ..
+    " resolution(s) and " .. #comment.lines .. " line(s) on " .. comment.url .. "\n"
+end
@@ -9,8 +9,21 @@
 )]
 
 //! Test all of the tests in chats/, which are .txt files formatted with IRC
-//! input beginning with <, expected IRC output beginning with >, and expected
-//! github output beginning with !.
+//! input beginning with <, expected IRC output beginning with >, expected
+//! github comments (and webhook notifications) beginning with !, and expected
+//! github label operations beginning with ~.  The github output is received
+//! by a mock github API server (which also receives the webhook
+//! notifications).
+//!
+//! In expected IRC output and github requests, [[CODE_DESCRIPTION]] stands
+//! for the bot's description of its code, and [[TODAY]] for the current
+//! (UTC) date.
+//!
+//! Lines beginning with @ are directives to the mock IRC server:
+//!   "@wait <duration>" (with a duration such as 90s, 61m, or 2h) makes time
+//!     pass (without actually waiting for it), so that timeouts fire.
+//!   "@reconnect" closes the bot's connection to the mock IRC server, so that
+//!     the rest of the chat happens over a new connection from the bot.
 
 use anyhow::Result;
 use futures::prelude::*;
@@ -18,21 +31,87 @@ use futures::task::Poll;
 use irc::client::prelude::{Client as IrcClient, Config as IrcConfig};
 use log::{debug, info};
 use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::Read;
 use std::path::Path;
 use std::str;
-use std::sync::LazyLock;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::TcpListener;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::time::{Duration, Instant};
 use wgmeeting_github_ircbot::*;
 
 const MOCK_SERVER_HOST: &str = "127.0.0.1";
 const MOCK_SERVER_PORT: u16 = 43210;
+const MOCK_GITHUB_PORT: u16 = 43211;
+const MOCK_GITHUB_URL: &str = "http://127.0.0.1:43211";
 
-#[tokio::test(flavor = "current_thread")]
-async fn test_chats() -> Result<()> {
+/// The lines the bot sends to identify itself to the IRC server when it connects.
+const IDENTIFY_LINES: &str = ">CAP END\r\n>NICK test-github-bot\r\n>USER dbaron-gh-bot 0 * :Bot to add meeting minutes to github issues.\r\n";
+
+const WAIT_DURATION: Duration = Duration::from_millis(100u64);
+const SERVER_SHUTDOWN_DURATION: Duration = Duration::from_millis(10u64);
+
+/// The labels that every issue in upsuper/labeled has in the mock github API server.
+const MOCK_LABELS: [&str; 3] = ["Agenda+", "Agenda+ F2F", "css-grid-2"];
+
+/// The labels that every repository has in the mock github API server.
+const MOCK_REPO_LABELS: [&str; 4] = ["Agenda+", "Agenda+ F2F", "css-grid-2", "Needs Testcase"];
+
+/// The open milestones in every repository in the mock github API server.
+const MOCK_MILESTONES: [&str; 2] = ["CSS Snapshot 2026", "Level 2"];
+
+/// Data about how many more lines we expect to receive from the bot (over IRC
+/// or through the github API) before sending the next input line.
+struct WaitLinesData {
+    expect_lines: i32,
+    wait_deadline: Instant,
+}
+
+impl WaitLinesData {
+    pub fn should_wait(&self) -> bool {
+        let time_remains = self.wait_deadline > Instant::now();
+        let result = self.expect_lines > 0 && time_remains;
+        debug!(
+            "should_wait: expect_lines={}, time_remains={} ==> {}",
+            self.expect_lines, time_remains, result
+        );
+        if !time_remains {
+            info!("wait for {} lines timed out", self.expect_lines);
+        }
+        result
+    }
+}
+
+/// Record a line received from the bot (in its escaped form) in the recording
+/// of the conversation, and note that we received it.
+fn record_received_line(
+    actual_lines: &RefCell<Vec<u8>>,
+    wait_lines_data: &RefCell<WaitLinesData>,
+    prefix: &str,
+    line: &str,
+) {
+    {
+        let mut wait_lines_data = wait_lines_data.borrow_mut();
+        wait_lines_data.expect_lines -= 1;
+    }
+
+    let mut actual_lines = actual_lines.borrow_mut();
+    actual_lines.append(&mut prefix.bytes().collect());
+    actual_lines.extend_from_slice(
+        line.chars()
+            .flat_map(|c| c.escape_default())
+            .collect::<String>()
+            .as_bytes(),
+    );
+    actual_lines.append(&mut "\r\n".bytes().collect());
+}
+
+#[test]
+fn test_chats() -> Result<()> {
     env_logger::init();
 
     let chats_dir = Path::new(file!()).parent().unwrap().join("chats");
@@ -40,7 +119,12 @@ async fn test_chats() -> Result<()> {
     let mut fail_count = 0;
     for direntry in chats_dir.read_dir()? {
         if let Ok(direntry) = direntry {
-            if !test_one_chat(direntry.path().as_path()).await? {
+            // Give each chat its own runtime, so that timers left over from one chat (such as
+            // activity timeouts) can't fire during a later one.
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?;
+            if !runtime.block_on(test_one_chat(direntry.path().as_path()))? {
                 fail_count += 1;
             }
         }
@@ -72,13 +156,32 @@ async fn test_one_chat(path: &Path) -> Result<bool> {
     .collect::<Vec<Vec<u8>>>();
 
     let is_finished = Cell::new(false);
+    let actual_lines = RefCell::new(Vec::<u8>::new());
+    let wait_lines_data = RefCell::new(WaitLinesData {
+        expect_lines: 3, // length of identify sequence
+        wait_deadline: Instant::now() + WAIT_DURATION,
+    });
 
-    let server = mock_irc_server(&chat_file_lines, &is_finished);
-    let bot = run_irc_bot(&is_finished);
+    let server = mock_irc_server(
+        &chat_file_lines,
+        &actual_lines,
+        &wait_lines_data,
+        &is_finished,
+    );
+    let github_server = mock_github_server(&actual_lines, &wait_lines_data, &is_finished);
+    // Only the chats about line processors get one, so that it can't affect the others.
+    let with_line_processor = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("line-processor-"));
+    let bot = run_irc_bot(&is_finished, with_line_processor);
 
-    let (actual_lines, bot_result) = future::join(server, bot).await;
+    let (server_result, github_server_result, bot_result) =
+        future::join3(server, github_server, bot).await;
     bot_result?;
-    let actual_lines = actual_lines?;
+    server_result?;
+    github_server_result?;
+    let actual_lines = actual_lines.into_inner();
 
     let actual_str = str::from_utf8(actual_lines.as_slice())?;
     let expected_lines = chat_lines_to_expected_lines(path, &chat_file_lines);
@@ -99,45 +202,48 @@ async fn test_one_chat(path: &Path) -> Result<bool> {
 }
 
 /// Run the fake IRC server for the chat test, driving the dialog based on the chat file.
-/// Record the entire conversation and return that recording for comparison with the expected
-/// result.
+/// Record the entire conversation in |actual_lines| for comparison with the expected result.
 async fn mock_irc_server(
     chat_file_lines: &Vec<Vec<u8>>,
+    actual_lines: &RefCell<Vec<u8>>,
+    wait_lines_data: &RefCell<WaitLinesData>,
     is_finished: &Cell<bool>,
-) -> Result<Vec<u8>> {
-    let actual_lines = RefCell::new(Vec::<u8>::new());
+) -> Result<()> {
+    let irc_server_addr = format!("{MOCK_SERVER_HOST}:{MOCK_SERVER_PORT}");
+    let irc_server_listener = TcpListener::bind(&irc_server_addr).await?;
 
-    struct WaitLinesData {
-        expect_lines: i32,
-        wait_deadline: Instant,
+    // Each @reconnect directive ends one connection from the bot; the rest of the chat happens
+    // over the next one.
+    let mut connections = chat_file_lines
+        .split(|line| line.as_slice() == b"@reconnect")
+        .peekable();
+    while let Some(connection_lines) = connections.next() {
+        let is_last_connection = connections.peek().is_none();
+        let (tcp_stream, _socket_addr) = irc_server_listener.accept().await?;
+        mock_irc_connection(
+            tcp_stream,
+            connection_lines,
+            is_last_connection,
+            actual_lines,
+            wait_lines_data,
+            is_finished,
+        )
+        .await?;
     }
 
-    impl WaitLinesData {
-        pub fn should_wait(&self) -> bool {
-            let time_remains = self.wait_deadline > Instant::now();
-            let result = self.expect_lines > 0 && time_remains;
-            debug!(
-                "should_wait: expect_lines={}, time_remains={} ==> {}",
-                self.expect_lines, time_remains, result
-            );
-            if !time_remains {
-                info!("wait for {} lines timed out", self.expect_lines);
-            }
-            result
-        }
-    }
-
-    const WAIT_DURATION: Duration = Duration::from_millis(100u64);
-    const SERVER_SHUTDOWN_DURATION: Duration = Duration::from_millis(10u64);
-
-    let wait_lines_data = RefCell::new(WaitLinesData {
-        expect_lines: 3, // length of identify sequence
-        wait_deadline: Instant::now() + WAIT_DURATION,
-    });
+    Ok(())
+}
 
-    let irc_server_addr = format!("{MOCK_SERVER_HOST}:{MOCK_SERVER_PORT}");
-    let irc_server_listener = TcpListener::bind(&irc_server_addr).await?;
-    let (mut tcp_stream, _socket_addr) = irc_server_listener.accept().await?;
+/// Handle one connection from the bot to the fake IRC server, driving the part of the dialog in
+/// |chat_file_lines|.
+async fn mock_irc_connection(
+    mut tcp_stream: TcpStream,
+    chat_file_lines: &[Vec<u8>],
+    is_last_connection: bool,
+    actual_lines: &RefCell<Vec<u8>>,
+    wait_lines_data: &RefCell<WaitLinesData>,
+    is_finished: &Cell<bool>,
+) -> Result<()> {
     tcp_stream.set_nodelay(true)?;
     debug!(
         "IRC server got incoming connection: nodelay={}",
@@ -153,22 +259,7 @@ async fn mock_irc_server(
             }
             debug!("IRC server read line: {}", line);
 
-            {
-                let mut wait_lines_data = wait_lines_data.borrow_mut();
-                wait_lines_data.expect_lines -= 1;
-            }
-
-            {
-                let mut actual_lines = actual_lines.borrow_mut();
-                actual_lines.append(&mut ">".bytes().collect());
-                actual_lines.extend_from_slice(
-                    line.chars()
-                        .flat_map(|c| c.escape_default())
-                        .collect::<String>()
-                        .as_bytes(),
-                );
-                actual_lines.append(&mut "\r\n".bytes().collect());
-            }
+            record_received_line(actual_lines, wait_lines_data, ">", &line);
         }
 
         Ok::<(), std::io::Error>(())
@@ -177,15 +268,15 @@ async fn mock_irc_server(
     let writer_future = async {
         for line in chat_file_lines.iter() {
             let first_char = line.first().map(|b| *b as char);
-            if first_char == Some('>') || first_char == Some('!') {
+            if first_char == Some('>') || first_char == Some('!') || first_char == Some('~') {
                 // This is a line we should expect to recieve from the bot.  Note this in
-                // |wait_lines_data|, which |reader_future| will use to adjust its timing.
+                // |wait_lines_data|, which |writer_future| will use to adjust its timing.
                 let mut wait_lines_data = wait_lines_data.borrow_mut();
                 wait_lines_data.expect_lines += 1;
                 wait_lines_data.wait_deadline = Instant::now() + WAIT_DURATION;
             }
 
-            if first_char != Some('<') {
+            if first_char != Some('<') && first_char != Some('@') {
                 continue;
             }
 
@@ -193,6 +284,28 @@ async fn mock_irc_server(
                 tokio::time::sleep(Duration::from_millis(1)).await;
             }
 
+            if first_char == Some('@') {
+                let directive = str::from_utf8(&line[1..])?;
+                {
+                    let mut actual_lines = actual_lines.borrow_mut();
+                    actual_lines.extend_from_slice(line);
+                    actual_lines.append(&mut "\r\n".bytes().collect());
+                }
+                match directive.split_once(' ') {
+                    Some(("wait", duration)) => {
+                        // Make the time pass instantly, but still fire any timers that it
+                        // passes.
+                        let duration = parse_wait_duration(duration)?;
+                        debug!("IRC server making {:?} pass", duration);
+                        tokio::time::pause();
+                        tokio::time::advance(duration).await;
+                        tokio::time::resume();
+                    }
+                    _ => anyhow::bail!("unknown directive @{}", directive),
+                }
+                continue;
+            }
+
             // note that line still begins with '<'
             // FIXME: Clean up this total hack for \u{1} !
             // (The other direction uses escape_default().)
@@ -209,10 +322,27 @@ async fn mock_irc_server(
             writer.write_all(line_str.as_bytes()).await?;
         }
 
-        tokio::time::sleep(SERVER_SHUTDOWN_DURATION).await;
+        // Wait for the lines we expect after the last line we send, too.
+        while wait_lines_data.borrow().should_wait() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        if is_last_connection {
+            tokio::time::sleep(SERVER_SHUTDOWN_DURATION).await;
 
-        debug!("SHUTTING DOWN THE SERVER");
-        is_finished.set(true);
+            debug!("SHUTTING DOWN THE SERVER");
+            is_finished.set(true);
+        } else {
+            debug!("CLOSING THE CONNECTION TO THE BOT");
+            {
+                let mut actual_lines = actual_lines.borrow_mut();
+                actual_lines.append(&mut "@reconnect\r\n".bytes().collect());
+            }
+            // The bot should identify itself again when it reconnects.
+            let mut wait_lines_data = wait_lines_data.borrow_mut();
+            wait_lines_data.expect_lines += 3;
+            wait_lines_data.wait_deadline = Instant::now() + WAIT_DURATION;
+        }
         // This seems (to my surprise) to be good enough to make the reader terminate as well.
         writer.shutdown().await?;
 
@@ -223,11 +353,785 @@ async fn mock_irc_server(
     reader_result?;
     writer_result?;
 
-    Ok(actual_lines.into_inner())
+    Ok(())
+}
+
+/// Parse the duration in a "@wait" directive, such as 90s, 61m, or 2h.
+fn parse_wait_duration(duration: &str) -> Result<Duration> {
+    let duration = duration.trim();
+    for (suffix, unit_secs) in [("s", 1), ("m", 60), ("h", 60 * 60)] {
+        if let Some(number) = duration.strip_suffix(suffix) {
+            return Ok(Duration::from_secs(number.parse::<u64>()? * unit_secs));
+        }
+    }
+    anyhow::bail!("unknown duration {:?} in @wait directive", duration)
+}
+
+/// Run the fake github API server for the chat test, recording the changes the bot makes on
+/// github in |actual_lines|.
+async fn mock_github_server(
+    actual_lines: &RefCell<Vec<u8>>,
+    wait_lines_data: &RefCell<WaitLinesData>,
+    is_finished: &Cell<bool>,
+) -> Result<()> {
+    let github_server_addr = format!("{MOCK_SERVER_HOST}:{MOCK_GITHUB_PORT}");
+    let github_server_listener = TcpListener::bind(&github_server_addr).await?;
+    while !is_finished.get() {
+        // Nothing wakes us up when the test finishes, so check periodically.
+        let accept_result =
+            tokio::time::timeout(Duration::from_millis(5), github_server_listener.accept()).await;
+        if let Ok(accept_result) = accept_result {
+            let (tcp_stream, _socket_addr) = accept_result?;
+            handle_github_request(tcp_stream, actual_lines, wait_lines_data).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle a single HTTP request to the fake github API server.
+async fn handle_github_request(
+    mut tcp_stream: TcpStream,
+    actual_lines: &RefCell<Vec<u8>>,
+    wait_lines_data: &RefCell<WaitLinesData>,
+) -> Result<()> {
+    let (reader, mut writer) = tcp_stream.split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    let _size = reader.read_line(&mut request_line).await?;
+    let mut content_length = 0;
+    let mut if_none_match = None;
+    loop {
+        let mut header = String::new();
+        let _size = reader.read_line(&mut header).await?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse()?;
+            } else if name.eq_ignore_ascii_case("if-none-match") {
+                if_none_match = Some(String::from(value.trim()));
+            }
+        }
+    }
+    let mut body = vec![0u8; content_length];
+    let _size = reader.read_exact(&mut body).await?;
+
+    let mut request_parts = request_line.split_whitespace();
+    let method = request_parts.next().unwrap_or("");
+    let path = request_parts.next().unwrap_or("");
+    let path = path.split('?').next().unwrap();
+    debug!("github server got request: {} {}", method, path);
+
+    let (mut status, mut response_body) =
+        mock_github_response(method, path, &body, &mut |prefix: &str, line: &str| {
+            record_received_line(actual_lines, wait_lines_data, prefix, line)
+        });
+    // Give everything an ETag, and answer conditional requests for things
+    // that haven't changed.
+    let etag = format!("\"{:016x}\"", {
+        let mut hasher = DefaultHasher::new();
+        response_body.hash(&mut hasher);
+        hasher.finish()
+    });
+    if method == "GET" && status == "200 OK" && if_none_match.as_ref() == Some(&etag) {
+        status = "304 Not Modified";
+        response_body = String::new();
+    }
+    writer
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: application/json; charset=utf-8\r\n\
+                 Content-Length: {}\r\nETag: {etag}\r\nConnection: close\r\n\r\n\
+                 {response_body}",
+                response_body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+    writer.shutdown().await?;
+
+    Ok(())
+}
+
+/// Compute the response of the fake github API server to a request, and call |record| with the
+/// prefix and text of each line that should be recorded (as a line beginning with ! or ~ in the
+/// chat file) as a result of the request.  Requests for anything in dbaron/nonexistentrepo fail
+/// as not found, requests for anything in upsuper/api-failure fail with an error, and comments
+/// in upsuper/readonly fail as forbidden.  Requests under /bugzilla and /jira are for a mock
+/// Bugzilla and JIRA, in which bug 404 and issue ABC-404 don't exist.
+fn mock_github_response(
+    method: &str,
+    path: &str,
+    body: &[u8],
+    record: &mut impl FnMut(&str, &str),
+) -> (&'static str, String) {
+    let segments = path.split('/').collect::<Vec<_>>();
+    match (method, segments.as_slice()) {
+        (_, ["", "repos", "dbaron", "nonexistentrepo", ..]) => (
+            "404 Not Found",
+            serde_json::json!({ "message": "Not Found" }).to_string(),
+        ),
+        (_, ["", "repos", "upsuper", "api-failure", ..]) => (
+            "422 Unprocessable Entity",
+            serde_json::json!({ "message": "Simulated failure" }).to_string(),
+        ),
+        ("GET", ["", "repos", owner, repo, "issues", number]) => {
+            ("200 OK", mock_issue(owner, repo, number).to_string())
+        }
+        ("PATCH", ["", "repos", owner, repo, "issues", number]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            if let Some(milestone) = request["milestone"].as_u64() {
+                let title = MOCK_MILESTONES[milestone as usize - 1];
+                record("~", &format!("SET MILESTONE {title} ON {url}"));
+            }
+            if let Some(state) = request["state"].as_str() {
+                record("~", &format!("SET STATE {state} ON {url}"));
+            }
+            ("200 OK", mock_issue(owner, repo, number).to_string())
+        }
+        ("GET", ["", "repos", owner, repo, "pulls", number]) => (
+            "200 OK",
+            serde_json::json!({
+                "id": 1,
+                "node_id": "MDExOlB1bGxSZXF1ZXN0MQ==",
+                "url": format!("https://api.github.com/repos/{owner}/{repo}/pulls/{number}"),
+                "html_url": format!("https://github.com/{owner}/{repo}/pull/{number}"),
+                "number": number.parse::<i64>().unwrap(),
+                "state": "open",
+                "title": "TITLE",
+                "body": "",
+                "labels": [],
+                "assignees": [],
+                "requested_reviewers": [],
+                "locked": false,
+                "draft": false,
+                "merged": false,
+                "mergeable": true,
+                "mergeable_state": "clean",
+                "head": {
+                    "label": "contributor:patch",
+                    "ref": "patch",
+                    "sha": "0123456789abcdef0123456789abcdef01234567",
+                    "user": null,
+                    "repo": null,
+                },
+                "base": {
+                    "label": format!("{owner}:main"),
+                    "ref": "main",
+                    "sha": "fedcba9876543210fedcba9876543210fedcba98",
+                    "user": null,
+                    "repo": null,
+                },
+                "created_at": "2017-05-01T00:00:00Z",
+                "updated_at": "2017-05-01T00:00:00Z",
+                "author_association": "NONE",
+            })
+            .to_string(),
+        ),
+        ("GET", ["", "repos", _owner, _repo, "pulls", _number, "reviews"]) => (
+            "200 OK",
+            serde_json::Value::Array(
+                [("fantasai", "APPROVED"), ("tabatkins", "CHANGES_REQUESTED"), ("tabatkins", "APPROVED"), ("emilio", "COMMENTED")]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (login, state))| {
+                        serde_json::json!({
+                            "id": i + 1,
+                            "node_id": format!("MDE3OlB1bGxSZXF1ZXN0UmV2aWV3{i}"),
+                            "user": { "login": login, "id": i + 1 },
+                            "body": "",
+                            "state": state,
+                            "commit_id": "0123456789abcdef0123456789abcdef01234567",
+                            "author_association": "NONE",
+                        })
+                    })
+                    .collect(),
+            )
+            .to_string(),
+        ),
+        ("GET", ["", "repos", owner, repo, "commits", sha, "status"]) => (
+            "200 OK",
+            serde_json::json!({
+                "state": "success",
+                "sha": sha,
+                "total_count": 1,
+                "statuses": [],
+                "repository": null,
+                "commit_url": format!("https://api.github.com/repos/{owner}/{repo}/commits/{sha}"),
+                "url": format!("https://api.github.com/repos/{owner}/{repo}/commits/{sha}/status"),
+            })
+            .to_string(),
+        ),
+        ("GET", ["", "agenda"]) => (
+            "200 OK",
+            String::from(
+                "<h1>Agenda</h1>\n\
+                 <ol>\n\
+                 <li><a href=\"https://github.com/dbaron/wgmeeting-github-ircbot/issues/5\">grid gaps</a>\n\
+                 (<a href=\"https://github.com/dbaron/wgmeeting-github-ircbot/issues/5#issuecomment-7\">latest proposal</a>)</li>\n\
+                 <li><a href=\"https://github.com/w3c/csswg-drafts/issues/9\">line-height</a></li>\n\
+                 <li>https://github.com/dbaron/wgmeeting-github-ircbot/pull/6</li>\n\
+                 </ol>\n",
+            ),
+        ),
+        ("POST", ["", "graphql"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let query = request["query"].as_str().unwrap();
+            let variables = &request["variables"];
+            if query.contains("removeLabelsFromLabelable") {
+                return mock_remove_labels(variables, record);
+            }
+            if query.contains("labels(first:") {
+                return mock_issue_labels(variables);
+            }
+            if query.contains("repositoryOwner(") {
+                return mock_owner_repos(variables);
+            }
+            if query.contains("viewerPermission") {
+                return mock_repository(variables);
+            }
+            let project = if variables["login"] == "w3c" && variables["number"] == 1 {
+                let issues = "https://github.com/dbaron/wgmeeting-github-ircbot/issues";
+                let items = [
+                    (Some(format!("{issues}/3")), "Agenda"),
+                    (Some(format!("{issues}/4")), "Done"),
+                    (None, "Agenda"),
+                    (
+                        Some("https://github.com/w3c/csswg-drafts/issues/9".to_string()),
+                        "Agenda",
+                    ),
+                    (Some(format!("{issues}/1")), "Agenda"),
+                    (
+                        Some("https://github.com/dbaron/wgmeeting-github-ircbot/pull/2".to_string()),
+                        "agenda",
+                    ),
+                ];
+                serde_json::json!({
+                    "title": "CSS Agenda",
+                    "items": {
+                        "nodes": items
+                            .iter()
+                            .map(|(url, status)| serde_json::json!({
+                                // Draft issues have no URL.
+                                "content": match url {
+                                    Some(url) => serde_json::json!({ "url": url }),
+                                    None => serde_json::json!({}),
+                                },
+                                "fieldValueByName": { "name": status },
+                            }))
+                            .collect::<Vec<_>>(),
+                    },
+                })
+            } else {
+                serde_json::Value::Null
+            };
+            (
+                "200 OK",
+                serde_json::json!({ "data": { "owner": { "projectV2": project } } }).to_string(),
+            )
+        }
+        ("GET", ["", "repos", owner, repo, "milestones"]) => (
+            "200 OK",
+            serde_json::Value::Array(
+                MOCK_MILESTONES
+                    .iter()
+                    .enumerate()
+                    .map(|(i, title)| {
+                        let number = i + 1;
+                        serde_json::json!({
+                            "id": number,
+                            "node_id": format!("MDk6TWlsZXN0b25l{number}"),
+                            "number": number,
+                            "url": format!("https://api.github.com/repos/{owner}/{repo}/milestones/{number}"),
+                            "html_url": format!("https://github.com/{owner}/{repo}/milestone/{number}"),
+                            "labels_url": format!("https://api.github.com/repos/{owner}/{repo}/milestones/{number}/labels"),
+                            "state": "open",
+                            "title": title,
+                            "description": "",
+                            "creator": null,
+                            "open_issues": 0,
+                            "closed_issues": 0,
+                            "created_at": "2017-05-01T00:00:00Z",
+                            "updated_at": "2017-05-01T00:00:00Z",
+                            "closed_at": null,
+                            "due_on": null,
+                        })
+                    })
+                    .collect(),
+            )
+            .to_string(),
+        ),
+        ("GET", ["", "repos", owner, repo, "issues", _, "labels"]) => {
+            let labels: &[&str] = if (*owner, *repo) == ("upsuper", "labeled") {
+                &MOCK_LABELS
+            } else {
+                &[]
+            };
+            ("200 OK", mock_labels(owner, repo, labels).to_string())
+        }
+        ("GET", ["", "repos", owner, repo, "labels"]) => (
+            "200 OK",
+            mock_labels(owner, repo, &MOCK_REPO_LABELS).to_string(),
+        ),
+        ("POST", ["", "repos", owner, repo, "issues", number, "labels"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            // The labels can be given as an array or as an object with a labels array.
+            let labels = match request.get("labels") {
+                Some(labels) => labels.as_array().unwrap().clone(),
+                None => request.as_array().unwrap().clone(),
+            };
+            let labels = labels
+                .iter()
+                .map(|label| label.as_str().unwrap())
+                .collect::<Vec<_>>();
+            for label in &labels {
+                record("~", &format!("ADD LABEL {label} ON {url}"));
+            }
+            ("200 OK", mock_labels(owner, repo, &labels).to_string())
+        }
+        ("PUT", ["", "repos", owner, repo, "issues", number, "lock"]) => {
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            let lock_reason = serde_json::from_slice::<serde_json::Value>(body)
+                .ok()
+                .and_then(|request| request["lock_reason"].as_str().map(String::from));
+            match lock_reason {
+                Some(lock_reason) => record("~", &format!("LOCK {url} ({lock_reason})")),
+                None => record("~", &format!("LOCK {url}")),
+            }
+            ("204 No Content", String::new())
+        }
+        ("DELETE", ["", "repos", owner, repo, "issues", number, "lock"]) => {
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            record("~", &format!("UNLOCK {url}"));
+            ("204 No Content", String::new())
+        }
+        // There are no files in any repository, so committing one creates it.
+        ("GET", ["", "repos", _, _, "contents", ..]) => (
+            "404 Not Found",
+            serde_json::json!({ "message": "Not Found" }).to_string(),
+        ),
+        ("PUT", ["", "repos", owner, repo, "contents", path @ ..]) => {
+            use base64::Engine;
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let content = base64::engine::general_purpose::STANDARD
+                .decode(request["content"].as_str().unwrap())
+                .unwrap();
+            let path = path.join("/");
+            let url = format!("https://github.com/{owner}/{repo}/blob/main/{path}");
+            record("!", &format!("!BEGIN GITHUB FILE {url}: {}", request["message"].as_str().unwrap()));
+            for line in String::from_utf8(content).unwrap().trim_end().split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END GITHUB FILE {url}"));
+            (
+                "201 Created",
+                serde_json::json!({ "content": { "html_url": url } }).to_string(),
+            )
+        }
+        ("DELETE", ["", "repos", owner, repo, "issues", number, "labels", label]) => {
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            record(
+                "~",
+                &format!("REMOVE LABEL {} ON {url}", percent_decode(label)),
+            );
+            ("200 OK", String::from("[]"))
+        }
+        // Nothing that we post is remembered, so there are never any comments,
+        // except for one that the bot posted long ago on issue 77.
+        ("GET", ["", "repos", owner, repo, "issues", "77", "comments"]) => (
+            "200 OK",
+            serde_json::json!([{
+                "id": 77,
+                "node_id": "MDEyOklzc3VlQ29tbWVudDc3",
+                "url": format!("https://api.github.com/repos/{owner}/{repo}/issues/comments/77"),
+                "html_url": format!("https://github.com/{owner}/{repo}/issues/77#issuecomment-77"),
+                "body": "The Bot-Testing Working Group just discussed `line-height`.\n\
+                         <!-- wgmeeting_github_ircbot comment 0123456789abcdef -->",
+                "created_at": "2017-05-01T00:00:00Z",
+                "updated_at": "2017-05-01T00:00:00Z",
+                "author_association": "NONE",
+            }])
+            .to_string(),
+        ),
+        ("GET", ["", "repos", _owner, _repo, "issues", _number, "comments"]) => {
+            ("200 OK", String::from("[]"))
+        }
+        // We can't comment in upsuper/readonly.
+        ("POST", ["", "repos", "upsuper", "readonly", "issues", _number, "comments"]) => (
+            "403 Forbidden",
+            serde_json::json!({ "message": "Resource not accessible by integration" })
+                .to_string(),
+        ),
+        ("POST", ["", "repos", owner, repo, "issues"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let title = request["title"].as_str().unwrap();
+            let url = format!("https://github.com/{owner}/{repo}");
+            record("!", &format!("!BEGIN GITHUB ISSUE IN {url}: {title}"));
+            for line in without_comment_marker(request["body"].as_str().unwrap()).split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END GITHUB ISSUE IN {url}"));
+            ("201 Created", mock_issue(owner, repo, "100").to_string())
+        }
+        ("POST", ["", "repos", owner, repo, "issues", number, "comments"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let comment_text = request["body"].as_str().unwrap();
+            let url = format!("https://github.com/{owner}/{repo}/issues/{number}");
+            record("!", &format!("!BEGIN GITHUB COMMENT IN {url}"));
+            for line in without_comment_marker(comment_text).split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END GITHUB COMMENT IN {url}"));
+            (
+                "201 Created",
+                serde_json::json!({
+                    "id": 1,
+                    "node_id": "MDEyOklzc3VlQ29tbWVudDE=",
+                    "url": format!("https://api.github.com/repos/{owner}/{repo}/issues/comments/1"),
+                    "html_url": format!("{url}#issuecomment-1"),
+                    "body": comment_text,
+                    "created_at": "2017-05-01T00:00:00Z",
+                    "updated_at": "2017-05-01T00:00:00Z",
+                    "author_association": "NONE",
+                })
+                .to_string(),
+            )
+        }
+        ("PATCH", ["", "repos", owner, repo, "issues", "comments", id]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let comment_text = request["body"].as_str().unwrap();
+            let url = format!("https://github.com/{owner}/{repo}");
+            record("!", &format!("!BEGIN GITHUB COMMENT {id} EDIT IN {url}"));
+            for line in without_comment_marker(comment_text).split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END GITHUB COMMENT {id} EDIT IN {url}"));
+            (
+                "200 OK",
+                serde_json::json!({
+                    "id": id.parse::<i64>().unwrap(),
+                    "node_id": "MDEyOklzc3VlQ29tbWVudDE=",
+                    "url": format!("https://api.github.com/repos/{owner}/{repo}/issues/comments/{id}"),
+                    "html_url": format!("{url}/issues/1#issuecomment-{id}"),
+                    "body": comment_text,
+                    "created_at": "2017-05-01T00:00:00Z",
+                    "updated_at": "2017-05-01T00:00:00Z",
+                    "author_association": "NONE",
+                })
+                .to_string(),
+            )
+        }
+        ("DELETE", ["", "repos", owner, repo, "issues", "comments", id]) => {
+            record(
+                "!",
+                &format!("!DELETE GITHUB COMMENT {id} IN https://github.com/{owner}/{repo}"),
+            );
+            ("204 No Content", String::new())
+        }
+        ("GET", ["", "bugzilla", "rest", "bug", "404"]) => (
+            "404 Not Found",
+            serde_json::json!({ "error": true, "code": 101 }).to_string(),
+        ),
+        ("GET", ["", "bugzilla", "rest", "bug", _id]) => (
+            "200 OK",
+            serde_json::json!({ "bugs": [{ "summary": "BUG TITLE" }] }).to_string(),
+        ),
+        ("POST", ["", "bugzilla", "rest", "bug", id, "comment"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let url = format!("https://bugs.example.org/show_bug.cgi?id={id}");
+            record("!", &format!("!BEGIN BUGZILLA COMMENT IN {url}"));
+            for line in request["comment"].as_str().unwrap().split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END BUGZILLA COMMENT IN {url}"));
+            ("201 Created", serde_json::json!({ "id": 1 }).to_string())
+        }
+        ("GET", ["", "jira", "rest", "api", "2", "issue", "ABC-404"]) => (
+            "404 Not Found",
+            serde_json::json!({ "errorMessages": ["Issue does not exist"] }).to_string(),
+        ),
+        ("GET", ["", "jira", "rest", "api", "2", "issue", _key]) => (
+            "200 OK",
+            serde_json::json!({ "fields": { "summary": "JIRA TITLE" } }).to_string(),
+        ),
+        ("POST", ["", "jira", "rest", "api", "2", "issue", key, "comment"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            let url = format!("https://example.atlassian.net/browse/{key}");
+            record("!", &format!("!BEGIN JIRA COMMENT IN {url}"));
+            for line in request["body"].as_str().unwrap().split('\n') {
+                record("!", line);
+            }
+            record("!", &format!("!END JIRA COMMENT IN {url}"));
+            ("201 Created", serde_json::json!({ "id": "10000" }).to_string())
+        }
+        ("POST", ["", "gists"]) => {
+            let request = serde_json::from_slice::<serde_json::Value>(body).unwrap();
+            record(
+                "!",
+                &format!(
+                    "!BEGIN GIST {} (public: {})",
+                    request["description"].as_str().unwrap(),
+                    request["public"]
+                ),
+            );
+            for line in request["files"]["irc-log.txt"]["content"]
+                .as_str()
+                .unwrap()
+                .split('\n')
+            {
+                record("!", line);
+            }
+            record("!", "!END GIST");
+            (
+                "201 Created",
+                serde_json::json!({ "html_url": "https://gist.github.com/mock/1" }).to_string(),
+            )
+        }
+        ("POST", ["", "webhook"]) => {
+            record("!", &format!("!WEBHOOK {}", str::from_utf8(body).unwrap()));
+            ("204 No Content", String::new())
+        }
+        _ => {
+            record("!", &format!("!UNEXPECTED REQUEST {method} {path}"));
+            (
+                "404 Not Found",
+                serde_json::json!({ "message": "Not Found" }).to_string(),
+            )
+        }
+    }
+}
+
+/// The text of a comment without the hidden marker (which is different
+/// every time) that the bot adds to the end of each comment.
+fn without_comment_marker(comment_text: &str) -> &str {
+    match comment_text.rsplit_once("\n<!-- wgmeeting_github_ircbot comment ") {
+        Some((text, _marker)) => text,
+        None => comment_text,
+    }
+}
+
+/// The JSON for a list of labels in the mock github API server.
+fn mock_labels(owner: &str, repo: &str, labels: &[&str]) -> serde_json::Value {
+    serde_json::Value::Array(
+        labels
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                serde_json::json!({
+                    "id": i + 1,
+                    "node_id": format!("MDU6TGFiZWwx{i}"),
+                    "url": format!("https://api.github.com/repos/{owner}/{repo}/labels/{name}"),
+                    "name": name,
+                    "description": "",
+                    "color": "ededed",
+                    "default": false,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// The response of the mock github API server to a GraphQL query for a
+/// repository and what the bot can do in it, where dbaron/nonexistentrepo
+/// doesn't exist.
+fn mock_repository(variables: &serde_json::Value) -> (&'static str, String) {
+    let owner = variables["owner"].as_str().unwrap();
+    let repo = variables["repo"].as_str().unwrap();
+    let response = match (owner, repo) {
+        ("dbaron", "nonexistentrepo") => serde_json::json!({
+            "data": { "repository": null },
+            "errors": [{
+                "message": format!("Could not resolve to a Repository with the name '{owner}/{repo}'."),
+            }],
+        }),
+        _ => serde_json::json!({
+            "data": {
+                "repository": {
+                    "nameWithOwner": format!("{owner}/{repo}"),
+                    "viewerPermission": "WRITE",
+                },
+            },
+        }),
+    };
+    ("200 OK", response.to_string())
+}
+
+/// The response of the mock github API server to a GraphQL query for the
+/// repositories of a user or organization, which (for upsuper) are the ones
+/// that the tests use.
+fn mock_owner_repos(variables: &serde_json::Value) -> (&'static str, String) {
+    let repository_owner = if variables["owner"] == "upsuper" {
+        serde_json::json!({
+            "repositories": {
+                "nodes": ["api-failure", "ircbot-test", "labeled"]
+                    .iter()
+                    .map(|name| serde_json::json!({ "name": name }))
+                    .collect::<Vec<_>>(),
+            },
+        })
+    } else {
+        serde_json::Value::Null
+    };
+    (
+        "200 OK",
+        serde_json::json!({ "data": { "repositoryOwner": repository_owner } }).to_string(),
+    )
+}
+
+/// The response of the mock github API server to a GraphQL query for the
+/// labels of an issue, where the IDs say which issue and label they are.
+/// Queries for issues in upsuper/api-failure fail with an error.
+fn mock_issue_labels(variables: &serde_json::Value) -> (&'static str, String) {
+    let owner = variables["owner"].as_str().unwrap();
+    let repo = variables["repo"].as_str().unwrap();
+    if (owner, repo) == ("upsuper", "api-failure") {
+        return (
+            "200 OK",
+            serde_json::json!({ "errors": [{ "message": "Simulated failure" }] }).to_string(),
+        );
+    }
+    let labels: &[&str] = if (owner, repo) == ("upsuper", "labeled") {
+        &MOCK_LABELS
+    } else {
+        &[]
+    };
+    let issue = serde_json::json!({
+        "id": format!("https://github.com/{owner}/{repo}/issues/{}", variables["number"]),
+        "labels": {
+            "nodes": labels
+                .iter()
+                .map(|name| serde_json::json!({ "id": format!("LABEL {name}"), "name": name }))
+                .collect::<Vec<_>>(),
+        },
+    });
+    (
+        "200 OK",
+        serde_json::json!({ "data": { "repository": { "issueOrPullRequest": issue } } })
+            .to_string(),
+    )
+}
+
+/// The response of the mock github API server to a GraphQL mutation that
+/// removes labels (with the IDs from mock_issue_labels) from an issue.
+fn mock_remove_labels(
+    variables: &serde_json::Value,
+    record: &mut impl FnMut(&str, &str),
+) -> (&'static str, String) {
+    let url = variables["issue"].as_str().unwrap();
+    for label in variables["labels"].as_array().unwrap() {
+        let label = label.as_str().unwrap().strip_prefix("LABEL ").unwrap();
+        record("~", &format!("REMOVE LABEL {label} ON {url}"));
+    }
+    (
+        "200 OK",
+        serde_json::json!({
+            "data": { "removeLabelsFromLabelable": { "clientMutationId": null } }
+        })
+        .to_string(),
+    )
+}
+
+/// The issue that the mock github API server returns for any issue number.
+/// Issues in upsuper/labeled also have an assignee and a milestone, and
+/// issue 78 is locked.
+fn mock_issue(owner: &str, repo: &str, number: &str) -> serde_json::Value {
+    let (assignees, milestone) = if (owner, repo) == ("upsuper", "labeled") {
+        (
+            serde_json::json!([{ "login": "fantasai", "id": 1 }]),
+            serde_json::json!({
+                "id": 2,
+                "number": 2,
+                "url": format!("https://api.github.com/repos/{owner}/{repo}/milestones/2"),
+                "html_url": format!("https://github.com/{owner}/{repo}/milestone/2"),
+                "state": "open",
+                "title": MOCK_MILESTONES[1],
+                "open_issues": 1,
+                "closed_issues": 0,
+                "created_at": "2017-05-01T00:00:00Z",
+                "updated_at": "2017-05-01T00:00:00Z",
+            }),
+        )
+    } else {
+        (serde_json::json!([]), serde_json::Value::Null)
+    };
+    serde_json::json!({
+        "id": 1,
+        "node_id": "MDU6SXNzdWUx",
+        "url": format!("https://api.github.com/repos/{owner}/{repo}/issues/{number}"),
+        "html_url": format!("https://github.com/{owner}/{repo}/issues/{number}"),
+        "number": number.parse::<i64>().unwrap(),
+        "state": "open",
+        "title": "TITLE",
+        "body": "",
+        "labels": [],
+        "assignees": assignees,
+        "milestone": milestone,
+        "locked": number == "78",
+        "active_lock_reason": (number == "78").then_some("too heated"),
+        "comments": 0,
+        "created_at": "2017-05-01T00:00:00Z",
+        "updated_at": "2017-05-01T00:00:00Z",
+        "author_association": "NONE",
+    })
+}
+
+/// Decode the %-escapes in a part of a URL path.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped_byte = if bytes[i] == b'%' && i + 3 <= bytes.len() {
+            str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped_byte {
+            Some(byte) => {
+                result.push(byte);
+                i += 3;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(result).unwrap()
+}
+
+/// A line processor for testing the line processor API.  It lists "ISSUE:" lines with the
+/// resolutions, turns "nick^:" into "nick:", and ignores everything said by "ignoreme".
+struct TestLineProcessor;
+
+impl LineProcessor for TestLineProcessor {
+    fn process_line(&self, _channel: &str, line: &mut ChannelLine) -> LineDisposition {
+        if line.source == "ignoreme" {
+            return LineDisposition::Ignore;
+        }
+        if let Some((nick, rest)) = line.message.split_once("^: ") {
+            if !nick.contains(' ') {
+                line.message = format!("{nick}: {rest}");
+            }
+        }
+        if line.message.starts_with("ISSUE:") {
+            LineDisposition::Summary
+        } else {
+            LineDisposition::Normal
+        }
+    }
 }
 
 /// Run the IRC bot side of the chat test (i.e., the code we're testing).
-async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
+async fn run_irc_bot(is_finished: &Cell<bool>, with_line_processor: bool) -> Result<()> {
     let irc_config = IrcConfig {
         use_mock_connection: false,
         owners: vec![format!("dbaron")],
@@ -249,7 +1153,7 @@ async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
         max_messages_in_burst: Some(50),
         ..Default::default()
     };
-    static BOT_CONFIG: LazyLock<BotConfig> = LazyLock::new(|| BotConfig {
+    let bot_config = Arc::new(BotConfig {
         source: "https://github.com/dbaron/wgmeeting-github-ircbot".to_string(),
         channels: vec![
             (
@@ -262,6 +1166,45 @@ async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
                         "upsuper/*".to_string(),
                     ],
                     publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: vec![(
+                        "css".to_string(),
+                        "https://github.com/orgs/w3c/projects/1".to_string(),
+                    )]
+                    .into_iter()
+                    .collect(),
+                    agenda_project_column: Some("Agenda".to_string()),
+                    agenda_page_url: Some(format!("{MOCK_GITHUB_URL}/agenda")),
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec!["ghbot".to_string()],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec!["https://bugs.example.org".to_string()],
+                    jira_urls_allowed: vec!["https://example.atlassian.net".to_string()],
                 },
             ),
             (
@@ -270,6 +1213,40 @@ async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
                     group: "Second Bot-Testing Working Group".to_string(),
                     github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
                     publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
                 },
             ),
             (
@@ -278,42 +1255,721 @@ async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
                     group: "Third Bot-Testing Working Group".to_string(),
                     github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
                     publish_resolutions_only: true,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testcommentscript".to_string(),
+                ChannelConfig {
+                    group: "Scripted Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: Some("tests/comment-script.lua".to_string()),
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testwebhook".to_string(),
+                ChannelConfig {
+                    group: "Webhook Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: Some(format!("{MOCK_GITHUB_URL}/webhook")),
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testconfirm".to_string(),
+                ChannelConfig {
+                    group: "Careful Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: true,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testdelay".to_string(),
+                ChannelConfig {
+                    group: "Patient Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 2,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testlogarchive".to_string(),
+                ChannelConfig {
+                    group: "Archived Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: Some(
+                        "https://irc.example.org/logs/testlogarchive".to_string(),
+                    ),
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testminutes".to_string(),
+                ChannelConfig {
+                    group: "Minuted Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: Some(format!(
+                        "{}/testminutes-{{date}}.html",
+                        env!("CARGO_TARGET_TMPDIR")
+                    )),
+                    minutes_url: Some(
+                        "https://irc.example.org/minutes/{date}-testminutes.html".to_string(),
+                    ),
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: true,
+                    github_reminder_lines: 5,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testobserve".to_string(),
+                ChannelConfig {
+                    group: "Observed Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: true,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testlinks".to_string(),
+                ChannelConfig {
+                    group: "Linked Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: true,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testnotice".to_string(),
+                ChannelConfig {
+                    group: "Quiet Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: Some(ResponseStyle::Notice),
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testsplit".to_string(),
+                ChannelConfig {
+                    group: "Split Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: true,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testgist".to_string(),
+                ChannelConfig {
+                    group: "Gisting Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 3,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testfallback".to_string(),
+                ChannelConfig {
+                    group: "Overflowing Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec![
+                        "dbaron/wgmeeting-github-ircbot".to_string(),
+                        "upsuper/readonly".to_string(),
+                    ],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: Some("upsuper/ircbot-test".to_string()),
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testminutesrepo".to_string(),
+                ChannelConfig {
+                    group: "Archiving Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: Some("upsuper/ircbot-test".to_string()),
+                    minutes_file_repo: None,
+                    minutes_file_path: None,
+                    minutes_file_only: false,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
+                },
+            ),
+            (
+                "#testminutesfile".to_string(),
+                ChannelConfig {
+                    group: "Minuting Bot-Testing Working Group".to_string(),
+                    github_repos_allowed: vec!["dbaron/wgmeeting-github-ircbot".to_string()],
+                    publish_resolutions_only: false,
+                    comment_script: None,
+                    webhook_url: None,
+                    confirm_before_posting: false,
+                    publish_delay_minutes: 0,
+                    log_archive_url: None,
+                    agenda_projects: HashMap::new(),
+                    agenda_project_column: None,
+                    agenda_page_url: None,
+                    meeting_times: vec![],
+                    agenda_reminder_minutes: None,
+                    minutes_path: None,
+                    minutes_url: None,
+                    digest_email: None,
+                    minutes_repo: None,
+                    minutes_file_repo: Some("upsuper/ircbot-test".to_string()),
+                    minutes_file_path: Some("minutes/{date}.md".to_string()),
+                    minutes_file_only: true,
+                    lint_after_meeting: false,
+                    github_reminder_lines: 0,
+                    observe_only: false,
+                    link_meeting_topics: false,
+                    anonymize_nicks: false,
+                    nick_roles: HashMap::new(),
+                    poll_minutes: 0,
+                    response_style: None,
+                    comment_per_resolution: false,
+                    gist_log_lines: 0,
+                    unlogged_nicks: vec![],
+                    command_aliases: HashMap::new(),
+                    address_names: vec![],
+                    spec_urls: HashMap::new(),
+                    fallback_repo: None,
+                    bugzilla_urls_allowed: vec![],
+                    jira_urls_allowed: vec![],
                 },
             ),
         ]
         .into_iter()
         .collect(),
-        // Use of a 0 value disables timeouts, which is needed to avoid intermittent
-        // failures (using really-0 timeouts) or having the event loop wait until the
-        // timeout completes (positive timeouts).
-        activity_timeout_minutes: 0,
+        // Time only passes this much during a chat when it has a @wait directive.
+        activity_timeout_minutes: 60,
+        github_access_token: "mock-github-token".to_string(),
+        bugzilla_api_keys: vec![(
+            "https://bugs.example.org".to_string(),
+            "mock-bugzilla-key".to_string(),
+        )]
+        .into_iter()
+        .collect(),
+        jira_credentials: vec![(
+            "https://example.atlassian.net".to_string(),
+            JiraCredentials {
+                email: "bot@example.org".to_string(),
+                api_token: "mock-jira-token".to_string(),
+            },
+        )]
+        .into_iter()
+        .collect(),
         owners: vec![format!("dbaron")],
+        irc_nicks: vec![
+            "test-github-bot".to_string(),
+            "test-github-bot-".to_string(),
+            "test-github-bot--".to_string(),
+        ],
+        instance_nicks: vec![
+            "primary-github-bot".to_string(),
+            "test-github-bot".to_string(),
+        ],
+        recent_resolution_days: 30,
+        default_channel_policy: true,
+        idle_part_days: 7,
+        max_topic_lines: Some(20),
         ..Default::default()
     });
 
-    let mut irc_state = IRCState::new(GithubType::MockGithubConnection);
+    let mut irc_state = IRCState::new(
+        GithubType::MockGithubConnection(MOCK_GITHUB_URL),
+        &bot_config,
+    );
+    if with_line_processor {
+        irc_state.add_line_processor(Box::new(TestLineProcessor));
+    }
 
-    let irc_client: &'static mut _ = Box::leak(Box::new(IrcClient::from_config(irc_config).await?));
+    // Connect to the server again (keeping the same state) whenever it closes the connection,
+    // until the test is finished.
+    while !is_finished.get() {
+        let mut irc_client = IrcClient::from_config(irc_config.clone()).await?;
 
-    irc_client.identify()?;
+        irc_client.identify()?;
 
-    let finished_cb = {
-        future::poll_fn(move |_cx| {
-            if is_finished.get() {
-                debug!("in take_until callback for messages stream: terminating");
-                Poll::Ready(())
-            } else {
-                debug!("in take_until callback for messages stream: continuing");
-                Poll::Pending
-            }
-        })
-    };
+        let finished_cb = {
+            future::poll_fn(move |_cx| {
+                if is_finished.get() {
+                    debug!("in take_until callback for messages stream: terminating");
+                    Poll::Ready(())
+                } else {
+                    debug!("in take_until callback for messages stream: continuing");
+                    Poll::Pending
+                }
+            })
+        };
 
-    let mut irc_stream = irc_client.stream()?.take_until(finished_cb);
-    while let Some(message) = irc_stream.next().await.transpose()? {
-        // This is where we call into the IRC bot's logic, i.e., where we call the code that's
-        // being tested here.
-        process_irc_message(irc_client, &mut irc_state, &BOT_CONFIG, message);
+        let mut irc_stream = irc_client.stream()?.take_until(finished_cb);
+        let irc_client = Arc::new(irc_client);
+        while let Some(message) = irc_stream.next().await {
+            match message {
+                // This is where we call into the IRC bot's logic, i.e., where we call the code
+                // that's being tested here.
+                Ok(message) => {
+                    process_irc_message(&irc_client, &mut irc_state, &bot_config, message)
+                }
+                Err(err) => {
+                    // The server closed the connection without finishing the test.
+                    info!("IRC connection failed: {}", err);
+                    break;
+                }
+            }
+        }
     }
 
     Ok(())
@@ -322,7 +1978,7 @@ async fn run_irc_bot(is_finished: &Cell<bool>) -> Result<()> {
 /// Convert the lines in the chat file to the dialog that the test should expect to have been
 /// recorded by the IRC server.
 fn chat_lines_to_expected_lines(path: &Path, chat_file_lines: &Vec<Vec<u8>>) -> Vec<u8> {
-    let mut expected_lines = ">CAP END\r\n>NICK test-github-bot\r\n>USER dbaron-gh-bot 0 * :Bot to add meeting minutes to github issues.\r\n".bytes().collect::<Vec<u8>>();
+    let mut expected_lines = IDENTIFY_LINES.bytes().collect::<Vec<u8>>();
 
     for line in chat_file_lines.iter() {
         match line.first().map(|b| *b as char) {
@@ -334,27 +1990,26 @@ fn chat_lines_to_expected_lines(path: &Path, chat_file_lines: &Vec<Vec<u8>>) ->
                 expected_lines.extend_from_slice(line.as_bytes());
                 expected_lines.append(&mut "\r\n".bytes().collect());
             }
-            Some('>') => {
+            Some('>') | Some('!') | Some('~') => {
+                // The mock github server records its lines as-is.
                 expected_lines.extend(
                     str::from_utf8(line)
                         .unwrap()
                         .replace("[[CODE_DESCRIPTION]]", code_description())
+                        .replace(
+                            "[[TODAY]]",
+                            &chrono::Utc::now().format("%Y-%m-%d").to_string(),
+                        )
                         .bytes(),
                 );
                 expected_lines.append(&mut "\r\n".bytes().collect());
             }
-            Some('!') => {
-                // for now, we send the github comments over IRC when
-                // testing, but we don't encode that into the chat
-                // format
-                expected_lines.append(&mut ">PRIVMSG github-comments ".bytes().collect());
-                // Match use of ":" in the stringify function in irc-proto's
-                // src/command.rs.
-                if line.len() == 1 || line.contains(&0x20u8 /* space */) {
-                    expected_lines.append(&mut ":".bytes().collect());
-                }
-                expected_lines.extend_from_slice(&line[1..]);
+            Some('@') => {
+                expected_lines.extend_from_slice(line);
                 expected_lines.append(&mut "\r\n".bytes().collect());
+                if line.as_slice() == b"@reconnect" {
+                    expected_lines.extend(IDENTIFY_LINES.bytes());
+                }
             }
             _ => {
                 panic!(